@@ -3,26 +3,32 @@
 
 //! Windows Core Audio device enumeration and control.
 
-use crate::config::AppResult;
+use crate::config::{AppResult, VOLUME_STEP_PERCENT};
 use crate::log_dbg;
+use crate::wave_format::{DeviceFormats, WaveFormat, WAVEFORMATEX_SIZE};
 use std::sync::{Arc, Mutex};
 use windows::core::{GUID, HSTRING, Interface, IUnknown, IUnknown_Vtbl, PCWSTR, HRESULT, Result};
-use windows::Win32::Foundation::BOOL;
+use windows::Win32::Foundation::{BOOL, S_OK};
 use windows::Win32::Media::Audio::{
-    IMMDeviceEnumerator, MMDeviceEnumerator, eRender, DEVICE_STATE_ACTIVE,
+    IMMDeviceEnumerator, MMDeviceEnumerator, eRender, eCapture, DEVICE_STATE_ACTIVE, DEVICE_STATE_DISABLED, DEVICE_STATE_NOTPRESENT, DEVICE_STATE_UNPLUGGED,
     IMMDeviceCollection, IMMDevice, eConsole, eMultimedia, eCommunications, ERole,
-    IMMNotificationClient_Vtbl, IMMNotificationClient, DEVICE_STATE, EDataFlow, WAVEFORMATEX
+    IMMNotificationClient_Vtbl, IMMNotificationClient, DEVICE_STATE, EDataFlow, WAVEFORMATEX,
+    IAudioClient, AUDCLNT_SHAREMODE_EXCLUSIVE,
 };
 use windows::Win32::System::Com::{
     CoInitializeEx, COINIT_APARTMENTTHREADED, CoCreateInstance, CLSCTX_ALL,
     CoTaskMemFree, STGM_READ
 };
+use windows::Win32::Media::Audio::Endpoints::{
+    IAudioEndpointVolume, IAudioEndpointVolumeCallback, IAudioEndpointVolumeCallback_Vtbl,
+};
+use windows::Win32::Media::Audio::AUDIO_VOLUME_NOTIFICATION_DATA;
 use windows::Win32::System::Com::StructuredStorage::{PROPVARIANT, PropVariantClear};
-use windows::Win32::System::Variant::VT_LPWSTR;
+use windows::Win32::System::Variant::{VT_CLSID, VT_LPWSTR, VT_UI4};
 
 // Manually define PKEY_Device_FriendlyName since it's not in the crate features
 #[repr(C)]
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[allow(clippy::upper_case_acronyms)]
 pub struct PROPERTYKEY {
     fmtid: GUID,
@@ -35,60 +41,554 @@ const PKEY_Device_FriendlyName: PROPERTYKEY = PROPERTYKEY {
     pid: 14,
 };
 
-/// Represents an audio output device.
+// Bus enumerator of the endpoint's device node ("HDAUDIO", "USB", "BTHENUM", ...)
+#[allow(non_upper_case_globals)]
+const PKEY_Device_EnumeratorName: PROPERTYKEY = PROPERTYKEY {
+    fmtid: GUID::from_u128(0xa45c254e_df1c_4efd_8020_67d146a850e0),
+    pid: 24,
+};
+
+// Generic endpoint description ("Speakers", "Headphones", "Microphone"); the friendly name adds the adapter
+#[allow(non_upper_case_globals)]
+const PKEY_Device_DeviceDesc: PROPERTYKEY = PROPERTYKEY {
+    fmtid: GUID::from_u128(0xa45c254e_df1c_4efd_8020_67d146a850e0),
+    pid: 2,
+};
+
+// EndpointFormFactor value (VT_UI4)
+#[allow(non_upper_case_globals)]
+const PKEY_AudioEndpoint_FormFactor: PROPERTYKEY = PROPERTYKEY {
+    fmtid: GUID::from_u128(0x1da5d803_d492_4edd_8c23_e0c0ffee7f0e),
+    pid: 0,
+};
+
+// Device format of the audio engine (changes when the "Default Format" is switched)
+#[allow(non_upper_case_globals)]
+const PKEY_AudioEngine_DeviceFormat: PROPERTYKEY = PROPERTYKEY {
+    fmtid: GUID::from_u128(0xf19f064d_082c_4e27_bc73_6882a1bb8e4c),
+    pid: 0,
+};
+
+// Physical device the endpoint belongs to (shared by a headset's stereo and hands-free endpoints)
+#[allow(non_upper_case_globals)]
+const PKEY_Device_ContainerId: PROPERTYKEY = PROPERTYKEY {
+    fmtid: GUID::from_u128(0x8c7ed206_3f8a_4827_b3ab_ae9e1faefc6c),
+    pid: 2,
+};
+
+/// Represents an audio endpoint (output or input).
 #[derive(Debug, Clone)]
 pub struct AudioDevice {
     pub id: String,
     pub name: String,
+    /// Default for Console / Multimedia (games, media, system sounds).
     pub is_default: bool,
+    /// Default for the Communications role (calls).
+    pub is_communications_default: bool,
+    /// Master volume (None if the endpoint does not expose `IAudioEndpointVolume`). Not read by
+    /// `enumerate`; the tray fills it in for the endpoints it lists.
+    pub volume: Option<EndpointVolume>,
+    /// User-defined display name from settings (None = friendly name).
+    pub alias: Option<String>,
+    pub connection: ConnectionType,
+    pub state: EndpointState,
+    /// `{...}` lowercase GUID of the physical device (None if not reported).
+    pub container_id: Option<String>,
+    pub form_factor: FormFactor,
+    /// Generic description, e.g. "Headphones" (None if not reported).
+    pub description: Option<String>,
+    /// Device format and accepted format choices; read by the tray for active endpoints (None = not read).
+    pub formats: Option<DeviceFormats>,
+}
+
+/// Endpoint availability (only `Active` endpoints can be made default).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EndpointState {
+    Active,
+    /// Jack unplugged, or Bluetooth device paired but not connected.
+    Unplugged,
+    /// Disabled in the Sound control panel (or from the tray).
+    Disabled,
+    /// Driver / device removed (only seen in change events, never enumerated).
+    NotPresent,
+}
+
+impl EndpointState {
+    fn from_device_state(state: DEVICE_STATE) -> Self {
+        match state {
+            DEVICE_STATE_UNPLUGGED => EndpointState::Unplugged,
+            DEVICE_STATE_DISABLED => EndpointState::Disabled,
+            DEVICE_STATE_NOTPRESENT => EndpointState::NotPresent,
+            _ => EndpointState::Active,
+        }
+    }
+
+    /// Menu suffix for inactive endpoints.
+    pub fn suffix(self) -> &'static str {
+        match self {
+            EndpointState::Active => "",
+            EndpointState::Unplugged => " · disconnected",
+            EndpointState::Disabled => " · disabled",
+            EndpointState::NotPresent => " · not present",
+        }
+    }
+}
+
+/// How an endpoint is attached, derived from its bus enumerator (HDMI / DisplayPort
+/// from the form factor, as they share the HD Audio bus with the codecs).
+/// Declaration order is the "by connection type" menu order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ConnectionType {
+    Builtin, // HD Audio codecs and other PCI audio
+    Hdmi,    // HDMI / DisplayPort audio of a graphics adapter
+    Usb,
+    Bluetooth,
+    Other,
+    Virtual, // software devices (streaming, virtual cables)
+}
+
+impl ConnectionType {
+    pub fn from_enumerator(enumerator: &str, form_factor: FormFactor) -> Self {
+        match enumerator.to_ascii_uppercase().as_str() {
+            "HDAUDIO" | "INTELAUDIO" | "PCI" if form_factor == FormFactor::Display => ConnectionType::Hdmi,
+            "HDAUDIO" | "INTELAUDIO" | "PCI" => ConnectionType::Builtin,
+            "USB" => ConnectionType::Usb,
+            "BTHENUM" | "BTHHFENUM" | "BTHLEDEVICE" => ConnectionType::Bluetooth,
+            "ROOT" | "SWD" | "SW" => ConnectionType::Virtual,
+            _ => ConnectionType::Other,
+        }
+    }
+
+    /// Name used by the `hide_type` setting.
+    pub fn name(self) -> &'static str {
+        match self {
+            ConnectionType::Builtin => "builtin",
+            ConnectionType::Hdmi => "hdmi",
+            ConnectionType::Usb => "usb",
+            ConnectionType::Bluetooth => "bluetooth",
+            ConnectionType::Other => "other",
+            ConnectionType::Virtual => "virtual",
+        }
+    }
+}
+
+/// Physical kind of endpoint (`PKEY_AudioEndpoint_FormFactor`).
+/// Declaration order is the "by type" menu order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FormFactor {
+    Speakers,
+    Headphones,
+    Headset,
+    Handset,
+    Microphone,
+    LineLevel,
+    Spdif,
+    Display, // HDMI / DisplayPort monitor or TV
+    Network,
+    Unknown,
 }
 
-/// Enumerate all active audio output devices.
-pub fn enumerate_audio_devices() -> AppResult<Vec<AudioDevice>> {
+impl FormFactor {
+    /// From an `EndpointFormFactor` value.
+    fn from_property(value: u32) -> Self {
+        match value {
+            0 => FormFactor::Network,
+            1 => FormFactor::Speakers,
+            2 => FormFactor::LineLevel,
+            3 => FormFactor::Headphones,
+            4 => FormFactor::Microphone,
+            5 => FormFactor::Headset,
+            6 => FormFactor::Handset,
+            7 | 8 => FormFactor::Spdif, // unknown digital passthrough / S/PDIF
+            9 => FormFactor::Display,
+            _ => FormFactor::Unknown,
+        }
+    }
+
+    /// Name used by the `hide_type` setting.
+    pub fn name(self) -> &'static str {
+        match self {
+            FormFactor::Speakers => "speakers",
+            FormFactor::Headphones => "headphones",
+            FormFactor::Headset => "headset",
+            FormFactor::Handset => "handset",
+            FormFactor::Microphone => "microphone",
+            FormFactor::LineLevel => "line",
+            FormFactor::Spdif => "spdif",
+            FormFactor::Display => "display",
+            FormFactor::Network => "network",
+            FormFactor::Unknown => "unknown",
+        }
+    }
+
+    /// Menu prefix (`audio_type_icons`).
+    pub fn icon(self) -> &'static str {
+        match self {
+            FormFactor::Speakers => "🔊",
+            FormFactor::Headphones | FormFactor::Headset | FormFactor::Handset => "🎧",
+            FormFactor::Microphone => "🎤",
+            FormFactor::LineLevel | FormFactor::Spdif => "🔌",
+            FormFactor::Display => "🖥",
+            FormFactor::Network => "🌐",
+            FormFactor::Unknown => "🔈",
+        }
+    }
+}
+
+impl AudioDevice {
+    /// Name shown in menus and notifications.
+    pub fn display_name(&self) -> &str { self.alias.as_deref().unwrap_or(&self.name) }
+
+    pub fn is_active(&self) -> bool { self.state == EndpointState::Active }
+
+    /// Bluetooth hands-free (HFP) endpoint: headset form factor, named "Headset (<device> Hands-Free[ AG Audio])" by Windows.
+    pub fn is_hands_free(&self) -> bool {
+        self.form_factor == FormFactor::Headset || self.name.to_ascii_lowercase().contains("hands-free")
+    }
+}
+
+#[cfg(test)]
+impl AudioDevice {
+    /// Active endpoint that is no default; tests adjust the rest with struct update syntax.
+    pub fn sample(id: &str, name: &str) -> Self {
+        Self {
+            id: id.to_string(), name: name.to_string(), is_default: false, is_communications_default: false,
+            volume: None, alias: None, connection: ConnectionType::Other, state: EndpointState::Active,
+            container_id: None, form_factor: FormFactor::Unknown, description: None, formats: None,
+        }
+    }
+}
+
+/// Master volume of an endpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EndpointVolume {
+    pub percent: u8,
+    pub muted: bool,
+}
+
+impl EndpointVolume {
+    /// Menu suffix, e.g. " · 40%" or " · muted".
+    pub fn suffix(self) -> String {
+        if self.muted { " · muted".to_string() } else { format!(" · {}%", self.percent) }
+    }
+
+    /// Volume after one step up / down, clamped to 0..=100.
+    pub fn stepped(self, up: bool) -> u8 {
+        if up { self.percent.saturating_add(VOLUME_STEP_PERCENT).min(100) } else { self.percent.saturating_sub(VOLUME_STEP_PERCENT) }
+    }
+}
+
+/// Endpoint direction: playback (render) or recording (capture).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioFlow {
+    Output,
+    Input,
+}
+
+impl AudioFlow {
+    fn as_edataflow(self) -> EDataFlow {
+        match self {
+            AudioFlow::Output => eRender,
+            AudioFlow::Input => eCapture,
+        }
+    }
+
+    fn from_edataflow(flow: EDataFlow) -> Self {
+        if flow == eCapture { AudioFlow::Input } else { AudioFlow::Output }
+    }
+}
+
+/// Active device after the current default in menu order, wrapping around; the
+/// first active device when none is the default. None if no device is active.
+pub fn next_device(devices: &[AudioDevice]) -> Option<&AudioDevice> {
+    let active: Vec<&AudioDevice> = devices.iter().filter(|d| d.is_active()).collect();
+    match active.iter().position(|d| d.is_default) {
+        Some(idx) => active.get((idx + 1) % active.len()).copied(),
+        None => active.first().copied(),
+    }
+}
+
+/// Roles a default endpoint can be assigned to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioRole {
+    Console,
+    Multimedia,
+    Communications,
+}
+
+impl AudioRole {
+    pub const ALL: [AudioRole; 3] = [AudioRole::Console, AudioRole::Multimedia, AudioRole::Communications];
+    /// Everything except calls (what the Sound panel calls "Default Device").
+    pub const MEDIA: [AudioRole; 2] = [AudioRole::Console, AudioRole::Multimedia];
+
+    fn as_erole(self) -> ERole {
+        match self {
+            AudioRole::Console => eConsole,
+            AudioRole::Multimedia => eMultimedia,
+            AudioRole::Communications => eCommunications,
+        }
+    }
+
+    fn from_erole(role: ERole) -> Self {
+        match role {
+            r if r == eMultimedia => AudioRole::Multimedia,
+            r if r == eCommunications => AudioRole::Communications,
+            _ => AudioRole::Console,
+        }
+    }
+}
+
+/// Keep-alive token for a change subscription (dropping it may stop notifications).
+pub type Subscription = Box<dyn std::any::Any>;
+
+/// Audio subsystem operations used by the tray and switching logic.
+/// `CoreAudioBackend` talks to Windows; `FakeAudioBackend` (`fake-backends`
+/// feature) keeps everything in memory so that logic runs without COM.
+pub trait AudioBackend: Send + Sync {
+    /// Active devices of `flow` with their console / communications default flags;
+    /// `include_inactive` adds unplugged and disabled endpoints.
+    fn enumerate(&self, flow: AudioFlow, include_inactive: bool) -> AppResult<Vec<AudioDevice>>;
+    /// Current default endpoint ID of `flow` for `role` (None if there is no such device).
+    fn default_device(&self, flow: AudioFlow, role: AudioRole) -> AppResult<Option<String>>;
+    /// Endpoint IDs are unique across flows, so no flow is needed to switch.
+    fn set_default(&self, device_id: &str, role: AudioRole) -> AppResult;
+    /// Invoke `callback` whenever devices appear / disappear, change state or
+    /// properties, or a default changes.
+    fn subscribe(&self, callback: DeviceChangeCallback) -> AppResult<Subscription>;
+
+    fn volume(&self, device_id: &str) -> AppResult<EndpointVolume>;
+    fn set_volume(&self, device_id: &str, percent: u8) -> AppResult;
+    fn set_muted(&self, device_id: &str, muted: bool) -> AppResult;
+    /// Invoke `callback` on volume / mute changes of the currently active endpoints
+    /// (from any application). Re-subscribe when the device list changes.
+    fn subscribe_volume(&self, callback: VolumeChangeCallback) -> AppResult<Subscription>;
+
+    /// Shared-mode device format ("Default Format" in the Sound control panel) and which
+    /// `FORMAT_CHOICES` the endpoint accepts; None if it reports no format.
+    fn device_formats(&self, device_id: &str) -> AppResult<Option<DeviceFormats>>;
+    /// Make `format` the device format; the mix format follows as 32-bit float at the same rate.
+    fn set_device_format(&self, device_id: &str, format: &WaveFormat) -> AppResult;
+    /// Enable or disable the endpoint system-wide (as "Enable" / "Disable" in the Sound control panel).
+    fn set_endpoint_enabled(&self, device_id: &str, enabled: bool) -> AppResult;
+
+    /// Make `device_id` the default for each of `roles`.
+    fn set_default_roles(&self, device_id: &str, roles: &[AudioRole]) -> AppResult {
+        for &role in roles { self.set_default(device_id, role)?; }
+        log_dbg!("audio: set {roles:?} default device to {device_id}");
+        Ok(())
+    }
+
+    /// Make `device_id` the default for all roles (Console, Multimedia, Communications).
+    fn set_default_all_roles(&self, device_id: &str) -> AppResult { self.set_default_roles(device_id, &AudioRole::ALL) }
+}
+
+/// Windows Core Audio (MMDevice API + IPolicyConfig) backend.
+pub struct CoreAudioBackend;
+
+fn create_enumerator() -> AppResult<IMMDeviceEnumerator> {
     unsafe {
         // Initialize COM
         let _ = CoInitializeEx(None, COINIT_APARTMENTTHREADED);
-        
         let enumerator: IMMDeviceEnumerator = CoCreateInstance(
             &MMDeviceEnumerator,
             None,
             CLSCTX_ALL,
-        )?;
+        ).map_err(|e| format!("Failed to create device enumerator: {:?}", e))?;
+        Ok(enumerator)
+    }
+}
+
+impl AudioBackend for CoreAudioBackend {
+    fn enumerate(&self, flow: AudioFlow, include_inactive: bool) -> AppResult<Vec<AudioDevice>> {
+        unsafe {
+            let enumerator = create_enumerator()?;
 
-        // Get default device ID for marking
-        let default_id = get_default_device_id(&enumerator).ok();
+            // Get default device IDs for marking
+            let defaults = DefaultIds {
+                console: self.default_device(flow, AudioRole::Console).ok().flatten(),
+                communications: self.default_device(flow, AudioRole::Communications).ok().flatten(),
+            };
 
-        let collection = enumerator.EnumAudioEndpoints(eRender, DEVICE_STATE_ACTIVE)?;
-        let count = collection.GetCount()?;
+            let states = if include_inactive {
+                DEVICE_STATE(DEVICE_STATE_ACTIVE.0 | DEVICE_STATE_UNPLUGGED.0 | DEVICE_STATE_DISABLED.0)
+            } else {
+                DEVICE_STATE_ACTIVE
+            };
+            let collection = enumerator.EnumAudioEndpoints(flow.as_edataflow(), states)?;
+            let count = collection.GetCount()?;
 
-        let mut devices = Vec::new();
-        for i in 0..count {
-            match get_device_info(&collection, i, default_id.as_ref()) {
-                Ok(device) => devices.push(device),
-                Err(_e) => { 
-                    log_dbg!("audio: failed to get device {i}: {:?}", _e);
+            let mut devices = Vec::new();
+            for i in 0..count {
+                match get_device_info(&collection, i, &defaults) {
+                    Ok(device) => devices.push(device),
+                    Err(_e) => { 
+                        log_dbg!("audio: failed to get device {i}: {:?}", _e);
+                    }
                 }
             }
+
+            log_dbg!("audio: enumerated {} {flow:?} devices", devices.len());
+            Ok(devices)
         }
+    }
 
-        log_dbg!("audio: enumerated {} output devices", devices.len());
-        Ok(devices)
+    fn default_device(&self, flow: AudioFlow, role: AudioRole) -> AppResult<Option<String>> {
+        let enumerator = create_enumerator()?;
+        // Fails with E_NOTFOUND when no device of this flow exists
+        match unsafe { enumerator.GetDefaultAudioEndpoint(flow.as_edataflow(), role.as_erole()) } {
+            Ok(device) => Ok(Some(unsafe { get_device_id(&device)? })),
+            Err(_) => Ok(None),
+        }
+    }
+
+    fn set_default(&self, device_id: &str, role: AudioRole) -> AppResult {
+        unsafe {
+            let device_id_hstring = HSTRING::from(device_id);
+            policy_config()?.SetDefaultEndpoint(&device_id_hstring, role.as_erole())
+                .map_err(|e| format!("Failed to set {:?} endpoint: {:?}", role, e))?;
+            Ok(())
+        }
+    }
+
+    fn subscribe(&self, callback: DeviceChangeCallback) -> AppResult<Subscription> {
+        Ok(Box::new(register_device_change_callback(callback)?))
+    }
+
+    fn volume(&self, device_id: &str) -> AppResult<EndpointVolume> {
+        unsafe { read_volume(&endpoint_volume(device_id)?) }
+    }
+
+    fn set_volume(&self, device_id: &str, percent: u8) -> AppResult {
+        unsafe {
+            endpoint_volume(device_id)?.SetMasterVolumeLevelScalar(f32::from(percent.min(100)) / 100.0, std::ptr::null())
+                .map_err(|e| format!("Failed to set volume: {:?}", e))?;
+        }
+        Ok(())
+    }
+
+    fn set_muted(&self, device_id: &str, muted: bool) -> AppResult {
+        unsafe {
+            endpoint_volume(device_id)?.SetMute(BOOL::from(muted), std::ptr::null())
+                .map_err(|e| format!("Failed to set mute: {:?}", e))?;
+        }
+        Ok(())
+    }
+
+    fn subscribe_volume(&self, callback: VolumeChangeCallback) -> AppResult<Subscription> {
+        unsafe {
+            let enumerator = create_enumerator()?;
+            let mut registrations = Vec::new();
+            for flow in [AudioFlow::Output, AudioFlow::Input] {
+                let collection = enumerator.EnumAudioEndpoints(flow.as_edataflow(), DEVICE_STATE_ACTIVE)?;
+                for i in 0..collection.GetCount()? {
+                    let Ok(volume) = collection.Item(i).and_then(|d| d.Activate::<IAudioEndpointVolume>(CLSCTX_ALL, None)) else { continue };
+                    let client = VolumeNotificationClient::create(Arc::clone(&callback));
+                    match volume.RegisterControlChangeNotify(&client) {
+                        Ok(()) => registrations.push((volume, client)),
+                        Err(_e) => { log_dbg!("audio: volume notification registration failed: {:?}", _e); }
+                    }
+                }
+            }
+            log_dbg!("audio: watching volume of {} endpoint(s)", registrations.len());
+            Ok(Box::new(VolumeSubscription(registrations)))
+        }
+    }
+
+    fn device_formats(&self, device_id: &str) -> AppResult<Option<DeviceFormats>> {
+        unsafe {
+            let policy_config = policy_config()?;
+            let id = HSTRING::from(device_id);
+            // Endpoints whose format was never set report only the engine's mix format
+            let format = policy_config.GetDeviceFormat(&id, false).or_else(|_| policy_config.GetMixFormat(&id))
+                .map_err(|e| format!("Failed to read device format: {:?}", e))?;
+            let Some(current) = take_wave_format(format) else { return Ok(None) };
+            // The Sound control panel offers the formats the endpoint accepts in exclusive mode
+            let client = get_device(device_id)?.Activate::<IAudioClient>(CLSCTX_ALL, None)?;
+            Ok(Some(DeviceFormats::probe(current, |candidate| {
+                client.IsFormatSupported(AUDCLNT_SHAREMODE_EXCLUSIVE, candidate.encode().as_ptr().cast(), None) == S_OK
+            })))
+        }
+    }
+
+    fn set_device_format(&self, device_id: &str, format: &WaveFormat) -> AppResult {
+        let (endpoint, mix) = (format.encode(), format.float_mix().encode());
+        unsafe {
+            policy_config()?.SetDeviceFormat(&HSTRING::from(device_id), endpoint.as_ptr().cast(), mix.as_ptr().cast())
+                .map_err(|e| format!("Failed to set device format: {:?}", e))?;
+        }
+        log_dbg!("audio: set format of {device_id} to {}", format.label());
+        Ok(())
+    }
+
+    fn set_endpoint_enabled(&self, device_id: &str, enabled: bool) -> AppResult {
+        unsafe {
+            policy_config()?.SetEndpointVisibility(&HSTRING::from(device_id), enabled)
+                .map_err(|e| format!("Failed to {} endpoint: {:?}", if enabled { "enable" } else { "disable" }, e))?;
+        }
+        log_dbg!("audio: {} {device_id}", if enabled { "enabled" } else { "disabled" });
+        Ok(())
     }
 }
 
+/// PolicyConfig instance (undocumented but widely used) for default endpoints and device formats.
+unsafe fn policy_config() -> AppResult<IPolicyConfig> {
+    let _ = CoInitializeEx(None, COINIT_APARTMENTTHREADED);
+    Ok(CoCreateInstance(&CLSID_POLICY_CONFIG, None, CLSCTX_ALL)
+        .map_err(|e| format!("Failed to create PolicyConfig COM instance: {:?}", e))?)
+}
+
+/// Parse and free a format returned by IPolicyConfig (None if null or not PCM / float).
+unsafe fn take_wave_format(format: *mut WAVEFORMATEX) -> Option<WaveFormat> {
+    if format.is_null() { return None; }
+    let extra = usize::from((&raw const (*format).cbSize).read_unaligned());
+    let parsed = WaveFormat::decode(std::slice::from_raw_parts(format.cast::<u8>(), WAVEFORMATEX_SIZE + extra));
+    CoTaskMemFree(Some(format as _));
+    parsed
+}
+
+/// Current default endpoint IDs of one flow, for marking enumerated devices.
+struct DefaultIds {
+    console: Option<String>,
+    communications: Option<String>,
+}
+
 /// Get device info for a specific index.
 unsafe fn get_device_info(
     collection: &IMMDeviceCollection,
     index: u32,
-    default_id: Option<&String>,
+    defaults: &DefaultIds,
 ) -> AppResult<AudioDevice> {
     let device = collection.Item(index)?;
     
     let id = get_device_id(&device)?;
     let name = get_device_name(&device, index)?;
-    let is_default = default_id.is_some_and(|d| d == &id);
+    let is_default = defaults.console.as_ref() == Some(&id);
+    let is_communications_default = defaults.communications.as_ref() == Some(&id);
+    let form_factor = get_u32_property(&device, &PKEY_AudioEndpoint_FormFactor).map_or(FormFactor::Unknown, FormFactor::from_property);
+    let connection = get_string_property(&device, &PKEY_Device_EnumeratorName)
+        .map_or(ConnectionType::Other, |e| ConnectionType::from_enumerator(&e, form_factor));
+    let description = get_string_property(&device, &PKEY_Device_DeviceDesc);
+    let state = device.GetState().map_or(EndpointState::Active, EndpointState::from_device_state);
+    let container_id = get_guid_property(&device, &PKEY_Device_ContainerId).map(|guid| format!("{{{guid:?}}}").to_ascii_lowercase());
+
+    Ok(AudioDevice { id, name, is_default, is_communications_default, volume: None, alias: None, connection, state, container_id, form_factor, description, formats: None })
+}
+
+/// The endpoint with the given ID.
+unsafe fn get_device(device_id: &str) -> AppResult<IMMDevice> {
+    Ok(create_enumerator()?.GetDevice(&HSTRING::from(device_id))
+        .map_err(|e| format!("Audio device not found: {:?}", e))?)
+}
+
+/// Endpoint volume control of the device with the given ID.
+unsafe fn endpoint_volume(device_id: &str) -> AppResult<IAudioEndpointVolume> {
+    Ok(get_device(device_id)?.Activate::<IAudioEndpointVolume>(CLSCTX_ALL, None)?)
+}
 
-    Ok(AudioDevice { id, name, is_default })
+unsafe fn read_volume(volume: &IAudioEndpointVolume) -> AppResult<EndpointVolume> {
+    let level = volume.GetMasterVolumeLevelScalar()?;
+    let muted = volume.GetMute()?.as_bool();
+    Ok(EndpointVolume { percent: (level * 100.0).round().clamp(0.0, 100.0) as u8, muted })
 }
 
 /// Get device ID string.
@@ -123,44 +623,48 @@ unsafe fn get_device_name(device: &IMMDevice, index: u32) -> AppResult<String> {
     Ok(name)
 }
 
-/// Get the current default audio device ID.
-fn get_default_device_id(enumerator: &IMMDeviceEnumerator) -> AppResult<String> {
-    unsafe {
-        let device = enumerator.GetDefaultAudioEndpoint(eRender, eConsole)?;
-        get_device_id(&device)
-    }
+/// String property of the endpoint (None if missing or not a string).
+unsafe fn get_string_property(device: &IMMDevice, key: &PROPERTYKEY) -> Option<String> {
+    let props = device.OpenPropertyStore(STGM_READ).ok()?;
+    let var = props.GetValue(std::ptr::from_ref(key).cast()).ok()?;
+    let value = if var.Anonymous.Anonymous.vt == VT_LPWSTR {
+        var.Anonymous.Anonymous.Anonymous.pwszVal.to_string().ok()
+    } else {
+        None
+    };
+    let _ = PropVariantClear((&raw const var).cast_mut());
+    value
 }
 
-// CLSID for PolicyConfig (used in set_default_audio_device)
-const CLSID_POLICY_CONFIG: GUID = GUID::from_u128(0x870af99c_171d_4f9e_af0d_e63df40c2bc9);
-
-/// Set the specified device as the default audio output.
-pub fn set_default_audio_device(device_id: &str) -> AppResult<()> {
-    unsafe {
-        let _ = CoInitializeEx(None, COINIT_APARTMENTTHREADED);
-        
-        // Use IPolicyConfig (undocumented but widely used) to set default device
-        let policy_config: IPolicyConfig = CoCreateInstance(
-            &CLSID_POLICY_CONFIG,
-            None,
-            CLSCTX_ALL,
-        ).map_err(|e| format!("Failed to create PolicyConfig COM instance: {:?}", e))?;
-
-        let device_id_hstring = HSTRING::from(device_id);
-        policy_config.SetDefaultEndpoint(&device_id_hstring, eConsole)
-            .map_err(|e| format!("Failed to set console endpoint: {:?}", e))?;
-        policy_config.SetDefaultEndpoint(&device_id_hstring, eMultimedia)
-            .map_err(|e| format!("Failed to set multimedia endpoint: {:?}", e))?;
-        policy_config.SetDefaultEndpoint(&device_id_hstring, eCommunications)
-            .map_err(|e| format!("Failed to set communications endpoint: {:?}", e))?;
-
-        log_dbg!("audio: set default device to {device_id}");
+/// Read a `VT_UI4` property; None if missing or of another type.
+unsafe fn get_u32_property(device: &IMMDevice, key: &PROPERTYKEY) -> Option<u32> {
+    let props = device.OpenPropertyStore(STGM_READ).ok()?;
+    let var = props.GetValue(std::ptr::from_ref(key).cast()).ok()?;
+    let value = if var.Anonymous.Anonymous.vt == VT_UI4 {
+        Some(var.Anonymous.Anonymous.Anonymous.ulVal)
+    } else {
+        None
+    };
+    let _ = PropVariantClear((&raw const var).cast_mut());
+    value
+}
 
-        log_dbg!("audio: set default device to {device_id}");
-        Ok(())
-    }
+/// Read a GUID (`VT_CLSID`) property; None if missing or of another type.
+unsafe fn get_guid_property(device: &IMMDevice, key: &PROPERTYKEY) -> Option<GUID> {
+    let props = device.OpenPropertyStore(STGM_READ).ok()?;
+    let var = props.GetValue(std::ptr::from_ref(key).cast()).ok()?;
+    let value = if var.Anonymous.Anonymous.vt == VT_CLSID {
+        var.Anonymous.Anonymous.Anonymous.puuid.as_ref().copied()
+    } else {
+        None
+    };
+    let _ = PropVariantClear((&raw const var).cast_mut());
+    value
 }
 
+// CLSID for PolicyConfig (see `policy_config`)
+const CLSID_POLICY_CONFIG: GUID = GUID::from_u128(0x870af99c_171d_4f9e_af0d_e63df40c2bc9);
+
 // ═══════════════════════════════════════════════════════════════════════════════
 // Undocumented IPolicyConfig COM Interface
 // Microsoft doesn't officially expose this, but it's the standard way to
@@ -261,6 +765,27 @@ pub struct IPolicyConfig_Vtbl {
 }
 
 impl IPolicyConfig {
+    /// Shared-mode mix format (`CoTaskMemAlloc`ed, free with `CoTaskMemFree`).
+    #[allow(non_snake_case)]
+    pub unsafe fn GetMixFormat(&self, device_id: &HSTRING) -> Result<*mut WAVEFORMATEX> {
+        let mut format = std::ptr::null_mut();
+        (Interface::vtable(self).GetMixFormat)(Interface::as_raw(self), PCWSTR(device_id.as_ptr()), &mut format).ok()?;
+        Ok(format)
+    }
+
+    /// Device format (`default` = the driver's default instead of the current one); free with `CoTaskMemFree`.
+    #[allow(non_snake_case)]
+    pub unsafe fn GetDeviceFormat(&self, device_id: &HSTRING, default: bool) -> Result<*mut WAVEFORMATEX> {
+        let mut format = std::ptr::null_mut();
+        (Interface::vtable(self).GetDeviceFormat)(Interface::as_raw(self), PCWSTR(device_id.as_ptr()), BOOL::from(default), &mut format).ok()?;
+        Ok(format)
+    }
+
+    #[allow(non_snake_case)]
+    pub unsafe fn SetDeviceFormat(&self, device_id: &HSTRING, endpoint_format: *const WAVEFORMATEX, mix_format: *const WAVEFORMATEX) -> Result<()> {
+        (Interface::vtable(self).SetDeviceFormat)(Interface::as_raw(self), PCWSTR(device_id.as_ptr()), endpoint_format, mix_format).ok()
+    }
+
     #[allow(non_snake_case)]
     pub unsafe fn SetDefaultEndpoint(&self, device_id: &HSTRING, role: ERole) -> Result<()> {
         (Interface::vtable(self).SetDefaultEndpoint)(
@@ -270,6 +795,11 @@ impl IPolicyConfig {
         )
         .ok()
     }
+
+    #[allow(non_snake_case)]
+    pub unsafe fn SetEndpointVisibility(&self, device_id: &HSTRING, visible: bool) -> Result<()> {
+        (Interface::vtable(self).SetEndpointVisibility)(Interface::as_raw(self), PCWSTR(device_id.as_ptr()), BOOL::from(visible)).ok()
+    }
 }
 
 // ═══════════════════════════════════════════════════════════════════════════════
@@ -278,8 +808,66 @@ impl IPolicyConfig {
 // connect/disconnect or the default device changes.
 // ═══════════════════════════════════════════════════════════════════════════════
 
+/// What changed, as reported by `IMMNotificationClient`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AudioDeviceEvent {
+    Added { id: String },
+    Removed { id: String },
+    StateChanged { id: String, state: EndpointState },
+    /// `id` is None when the last device of `flow` went away.
+    DefaultChanged { flow: AudioFlow, role: AudioRole, id: Option<String> },
+    PropertyChanged { id: String, key: PROPERTYKEY },
+}
+
+impl AudioDeviceEvent {
+    /// Whether the audio menus can look different afterwards. Property changes
+    /// other than the friendly name and device format are noise (volume, jack info, caches),
+    /// and the Multimedia default always moves together with Console.
+    pub fn affects_menu(&self) -> bool {
+        match self {
+            AudioDeviceEvent::PropertyChanged { key, .. } => *key == PKEY_Device_FriendlyName || *key == PKEY_AudioEngine_DeviceFormat,
+            AudioDeviceEvent::DefaultChanged { role, .. } => *role != AudioRole::Multimedia,
+            _ => true,
+        }
+    }
+
+    /// Endpoint whose stream format may differ afterwards (format property, or the endpoint
+    /// came, went or changed state), if any.
+    pub fn format_changed(&self) -> Option<&str> {
+        match self {
+            AudioDeviceEvent::PropertyChanged { id, key } if *key == PKEY_AudioEngine_DeviceFormat => Some(id),
+            AudioDeviceEvent::Added { id } | AudioDeviceEvent::Removed { id } | AudioDeviceEvent::StateChanged { id, .. } => Some(id),
+            _ => None,
+        }
+    }
+
+    /// Endpoint that stopped being usable (removed, unplugged, disabled), if any.
+    pub fn departed(&self) -> Option<&str> {
+        match self {
+            AudioDeviceEvent::Removed { id } => Some(id),
+            AudioDeviceEvent::StateChanged { id, state } if *state != EndpointState::Active => Some(id),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for AudioDeviceEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AudioDeviceEvent::Added { id } => write!(f, "added {id}"),
+            AudioDeviceEvent::Removed { id } => write!(f, "removed {id}"),
+            AudioDeviceEvent::StateChanged { id, state } => write!(f, "{id} is now {state:?}"),
+            AudioDeviceEvent::DefaultChanged { flow, role, id } => write!(f, "{flow:?} {role:?} default -> {}", id.as_deref().unwrap_or("none")),
+            AudioDeviceEvent::PropertyChanged { id, key } => write!(f, "{id} property {:?}/{}", key.fmtid, key.pid),
+        }
+    }
+}
+
 /// Callback type for when audio devices change
-pub type DeviceChangeCallback = Arc<dyn Fn() + Send + Sync>;
+pub type DeviceChangeCallback = Arc<dyn Fn(AudioDeviceEvent) + Send + Sync>;
+
+/// Callback type for endpoint volume / mute changes
+pub type VolumeChangeCallback = Arc<dyn Fn() + Send + Sync>;
 
 /// COM notification client for audio device state changes.
 /// Uses manual vtable implementation since windows-rs implement macro has limitations.
@@ -360,67 +948,66 @@ unsafe extern "system" fn release(this: *mut std::ffi::c_void) -> u32 {
 
 unsafe extern "system" fn on_device_state_changed(
     this: *mut std::ffi::c_void,
-    _device_id: PCWSTR,
-    _new_state: DEVICE_STATE,
+    device_id: PCWSTR,
+    new_state: DEVICE_STATE,
 ) -> HRESULT {
-    let client = &*(this as *const DeviceNotificationClient);
-    log_dbg!("audio: device state changed");
-    (client.callback)();
+    let state = EndpointState::from_device_state(new_state);
+    notify_client(this, AudioDeviceEvent::StateChanged { id: pcwstr_to_string(device_id), state });
     HRESULT(0)
 }
 
 unsafe extern "system" fn on_device_added(
     this: *mut std::ffi::c_void,
-    _device_id: PCWSTR,
+    device_id: PCWSTR,
 ) -> HRESULT {
-    let client = &*(this as *const DeviceNotificationClient);
-    log_dbg!("audio: device added");
-    (client.callback)();
+    notify_client(this, AudioDeviceEvent::Added { id: pcwstr_to_string(device_id) });
     HRESULT(0)
 }
 
 unsafe extern "system" fn on_device_removed(
     this: *mut std::ffi::c_void,
-    _device_id: PCWSTR,
+    device_id: PCWSTR,
 ) -> HRESULT {
-    let client = &*(this as *const DeviceNotificationClient);
-    log_dbg!("audio: device removed");
-    (client.callback)();
+    notify_client(this, AudioDeviceEvent::Removed { id: pcwstr_to_string(device_id) });
     HRESULT(0)
 }
 
 unsafe extern "system" fn on_default_device_changed(
     this: *mut std::ffi::c_void,
-    _flow: EDataFlow,
-    _role: ERole,
-    _default_device_id: PCWSTR,
+    flow: EDataFlow,
+    role: ERole,
+    default_device_id: PCWSTR,
 ) -> HRESULT {
-    let client = &*(this as *const DeviceNotificationClient);
-    log_dbg!("audio: default device changed");
-    (client.callback)();
+    let id = (!default_device_id.is_null()).then(|| pcwstr_to_string(default_device_id));
+    notify_client(this, AudioDeviceEvent::DefaultChanged { flow: AudioFlow::from_edataflow(flow), role: AudioRole::from_erole(role), id });
     HRESULT(0)
 }
 
 unsafe extern "system" fn on_property_value_changed(
-    _this: *mut std::ffi::c_void,
-    _device_id: PCWSTR,
-    _key: windows::Win32::Foundation::PROPERTYKEY,
+    this: *mut std::ffi::c_void,
+    device_id: PCWSTR,
+    key: windows::Win32::Foundation::PROPERTYKEY,
 ) -> HRESULT {
-    // Don't trigger on every property change (too noisy)
+    let key = PROPERTYKEY { fmtid: key.fmtid, pid: key.pid };
+    notify_client(this, AudioDeviceEvent::PropertyChanged { id: pcwstr_to_string(device_id), key });
     HRESULT(0)
 }
 
+/// Forward an event to the client's callback (runs on a Core Audio worker thread).
+unsafe fn notify_client(this: *mut std::ffi::c_void, event: AudioDeviceEvent) {
+    let client = &*(this as *const DeviceNotificationClient);
+    (client.callback)(event);
+}
+
+unsafe fn pcwstr_to_string(text: PCWSTR) -> String {
+    if text.is_null() { String::new() } else { text.to_string().unwrap_or_default() }
+}
+
 /// Register for audio device change notifications.
 /// Returns a handle that must be kept alive to continue receiving notifications.
-pub fn register_device_change_callback(callback: DeviceChangeCallback) -> AppResult<IMMNotificationClient> {
+fn register_device_change_callback(callback: DeviceChangeCallback) -> AppResult<IMMNotificationClient> {
     unsafe {
-        let _ = CoInitializeEx(None, COINIT_APARTMENTTHREADED);
-        
-        let enumerator: IMMDeviceEnumerator = CoCreateInstance(
-            &MMDeviceEnumerator,
-            None,
-            CLSCTX_ALL,
-        ).map_err(|e| format!("Failed to create device enumerator: {:?}", e))?;
+        let enumerator = create_enumerator()?;
 
         let client = DeviceNotificationClient::create(callback);
         enumerator.RegisterEndpointNotificationCallback(&client)
@@ -428,8 +1015,168 @@ pub fn register_device_change_callback(callback: DeviceChangeCallback) -> AppRes
         
         log_dbg!("audio: registered device change notification callback");
         
-        log_dbg!("audio: registered device change notification callback");
-        
         Ok(client)
     }
 }
+
+// ═══════════════════════════════════════════════════════════════════════════════
+// IAudioEndpointVolumeCallback - per-endpoint volume / mute change notifications
+// Same manual vtable approach as the device notification client above.
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[repr(C)]
+struct VolumeNotificationClient {
+    vtable: *const IAudioEndpointVolumeCallback_Vtbl,
+    ref_count: Mutex<u32>,
+    callback: VolumeChangeCallback,
+}
+
+impl VolumeNotificationClient {
+    fn create(callback: VolumeChangeCallback) -> IAudioEndpointVolumeCallback {
+        let client = Box::new(VolumeNotificationClient {
+            vtable: &raw const VOLUME_NOTIFICATION_VTABLE,
+            ref_count: Mutex::new(1),
+            callback,
+        });
+        unsafe { IAudioEndpointVolumeCallback::from_raw(Box::into_raw(client).cast()) }
+    }
+}
+
+static VOLUME_NOTIFICATION_VTABLE: IAudioEndpointVolumeCallback_Vtbl = IAudioEndpointVolumeCallback_Vtbl {
+    base__: IUnknown_Vtbl {
+        QueryInterface: volume_query_interface,
+        AddRef: volume_add_ref,
+        Release: volume_release,
+    },
+    OnNotify: on_volume_notify,
+};
+
+unsafe extern "system" fn volume_query_interface(
+    this: *mut std::ffi::c_void,
+    iid: *const GUID,
+    object: *mut *mut std::ffi::c_void,
+) -> HRESULT {
+    let iid = &*iid;
+    if iid == &IAudioEndpointVolumeCallback::IID || iid == &IUnknown::IID {
+        *object = this;
+        volume_add_ref(this);
+        HRESULT(0)
+    } else {
+        *object = std::ptr::null_mut();
+        const E_NOINTERFACE: i32 = 0x8000_4002_u32 as i32;
+        HRESULT(E_NOINTERFACE)
+    }
+}
+
+unsafe extern "system" fn volume_add_ref(this: *mut std::ffi::c_void) -> u32 {
+    let client = &*(this as *const VolumeNotificationClient);
+    let mut count = client.ref_count.lock().unwrap();
+    *count += 1;
+    *count
+}
+
+unsafe extern "system" fn volume_release(this: *mut std::ffi::c_void) -> u32 {
+    let client = &*(this.cast::<VolumeNotificationClient>());
+    let count = {
+        let mut count = client.ref_count.lock().unwrap();
+        *count -= 1;
+        *count
+    };
+    if count == 0 {
+        let _ = Box::from_raw(this.cast::<VolumeNotificationClient>());
+    }
+    count
+}
+
+unsafe extern "system" fn on_volume_notify(
+    this: *mut std::ffi::c_void,
+    _data: *mut AUDIO_VOLUME_NOTIFICATION_DATA,
+) -> HRESULT {
+    let client = &*(this as *const VolumeNotificationClient);
+    (client.callback)();
+    HRESULT(0)
+}
+
+/// Registered volume callbacks; unregistered on drop.
+struct VolumeSubscription(Vec<(IAudioEndpointVolume, IAudioEndpointVolumeCallback)>);
+
+impl Drop for VolumeSubscription {
+    fn drop(&mut self) {
+        for (volume, client) in &self.0 {
+            unsafe { let _ = volume.UnregisterControlChangeNotify(client); }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(id: &str, is_default: bool, state: EndpointState) -> AudioDevice {
+        AudioDevice { is_default, state, ..AudioDevice::sample(id, id) }
+    }
+
+    fn next_id(devices: &[AudioDevice]) -> Option<&str> { next_device(devices).map(|d| d.id.as_str()) }
+
+    #[test]
+    fn next_device_follows_the_default_and_wraps() {
+        let devices = [
+            device("a", false, EndpointState::Active),
+            device("b", true, EndpointState::Active),
+            device("c", false, EndpointState::Active),
+        ];
+        assert_eq!(next_id(&devices), Some("c"));
+        let devices = [
+            device("a", false, EndpointState::Active),
+            device("b", false, EndpointState::Active),
+            device("c", true, EndpointState::Active),
+        ];
+        assert_eq!(next_id(&devices), Some("a"));
+    }
+
+    #[test]
+    fn next_device_skips_inactive_devices() {
+        let devices = [
+            device("a", true, EndpointState::Active),
+            device("b", false, EndpointState::Unplugged),
+            device("c", false, EndpointState::Disabled),
+            device("d", false, EndpointState::Active),
+        ];
+        assert_eq!(next_id(&devices), Some("d"));
+    }
+
+    #[test]
+    fn next_device_without_default_starts_at_first_active() {
+        let devices = [device("a", false, EndpointState::Unplugged), device("b", false, EndpointState::Active)];
+        assert_eq!(next_id(&devices), Some("b"));
+    }
+
+    #[test]
+    fn next_device_with_single_device_stays() {
+        assert_eq!(next_id(&[device("a", true, EndpointState::Active)]), Some("a"));
+    }
+
+    #[test]
+    fn next_device_none_when_nothing_is_active() {
+        assert_eq!(next_id(&[]), None);
+        let devices = [device("a", true, EndpointState::Unplugged), device("b", false, EndpointState::Disabled)];
+        assert_eq!(next_id(&devices), None);
+    }
+
+    #[test]
+    fn format_changes_are_attributed_to_their_endpoint() {
+        let id = "{0.0.0.00000000}.{aaaa}".to_string();
+        let format = AudioDeviceEvent::PropertyChanged { id: id.clone(), key: PKEY_AudioEngine_DeviceFormat };
+        let name = AudioDeviceEvent::PropertyChanged { id: id.clone(), key: PKEY_Device_FriendlyName };
+        let unplugged = AudioDeviceEvent::StateChanged { id: id.clone(), state: EndpointState::Unplugged };
+        let added = AudioDeviceEvent::Added { id: id.clone() };
+        let removed = AudioDeviceEvent::Removed { id: id.clone() };
+        let default = AudioDeviceEvent::DefaultChanged { flow: AudioFlow::Output, role: AudioRole::Console, id: Some(id.clone()) };
+        assert_eq!(format.format_changed(), Some(id.as_str()));
+        assert_eq!(unplugged.format_changed(), Some(id.as_str()));
+        assert_eq!(added.format_changed(), Some(id.as_str()));
+        assert_eq!(removed.format_changed(), Some(id.as_str()));
+        assert_eq!(name.format_changed(), None);
+        assert_eq!(default.format_changed(), None);
+    }
+}
@@ -0,0 +1,355 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2025 Triffit
+
+//! In-memory `AudioBackend` (`fake-backends` feature, and unit tests).
+//! Holds output / input device lists, per-role defaults and volumes; the
+//! scripting methods simulate devices appearing, disappearing and defaults or
+//! volumes changing behind the app's back, notifying subscribers exactly like
+//! `IMMNotificationClient` / `IAudioEndpointVolumeCallback` would.
+
+use std::sync::Mutex;
+use crate::audio_device::{
+    AudioBackend, AudioDevice, AudioDeviceEvent, AudioFlow, AudioRole, ConnectionType, DeviceChangeCallback, EndpointState,
+    EndpointVolume, FormFactor, Subscription, VolumeChangeCallback,
+};
+use crate::config::AppResult;
+use crate::wave_format::{DeviceFormats, WaveFormat};
+
+/// Highest sample rate fake endpoints accept, so the tray has a choice to grey out.
+const FAKE_MAX_SAMPLE_RATE: u32 = 48_000;
+
+struct FakeDevice {
+    flow: AudioFlow,
+    id: String,
+    name: String,
+    connection: ConnectionType,
+    form_factor: FormFactor,
+    state: EndpointState,
+    volume: EndpointVolume,
+    container_id: Option<String>,
+    format: WaveFormat,
+}
+
+#[derive(Default)]
+struct FakeState {
+    devices: Vec<FakeDevice>,           // in enumeration order
+    defaults: [[Option<String>; 3]; 2], // [flow_index][role_index]
+    subscribers: Vec<DeviceChangeCallback>,
+    volume_subscribers: Vec<VolumeChangeCallback>,
+}
+
+impl FakeState {
+    fn device_mut(&mut self, id: &str) -> AppResult<&mut FakeDevice> {
+        self.devices.iter_mut().find(|d| d.id == id).ok_or_else(|| format!("Unknown fake device {id}").into())
+    }
+}
+
+#[derive(Default)]
+pub struct FakeAudioBackend {
+    state: Mutex<FakeState>,
+}
+
+fn role_index(role: AudioRole) -> usize {
+    AudioRole::ALL.iter().position(|&r| r == role).unwrap_or(0)
+}
+
+fn flow_index(flow: AudioFlow) -> usize {
+    match flow { AudioFlow::Output => 0, AudioFlow::Input => 1 }
+}
+
+impl FakeAudioBackend {
+    /// Speakers / microphone (default) plus a Bluetooth headset with stereo and hands-free
+    /// endpoints, for UI work without sound cards.
+    pub fn with_sample_devices() -> Self {
+        const HEADSET: &str = "{fa4e0000-0000-0000-0000-000000000001}";
+        let fake = Self::default();
+        fake.add_device(AudioFlow::Output, "{fake}.speakers", "Speakers (Fake Audio)", ConnectionType::Builtin, FormFactor::Speakers);
+        fake.add_device(AudioFlow::Output, "{fake}.headphones", "Headphones (Fake Headset)", ConnectionType::Bluetooth, FormFactor::Headphones);
+        fake.add_device(AudioFlow::Output, "{fake}.headset", "Headset (Fake Headset Hands-Free)", ConnectionType::Bluetooth, FormFactor::Headset);
+        fake.add_device(AudioFlow::Input, "{fake}.microphone", "Microphone (Fake Audio)", ConnectionType::Builtin, FormFactor::Microphone);
+        fake.add_device(AudioFlow::Input, "{fake}.headset-mic", "Headset (Fake Headset Hands-Free)", ConnectionType::Bluetooth, FormFactor::Headset);
+        for id in ["{fake}.headphones", "{fake}.headset", "{fake}.headset-mic"] { fake.set_container_id(id, HEADSET); }
+        fake
+    }
+
+    /// Run subscribers outside the lock (they may call back into the backend).
+    fn notify(&self, event: AudioDeviceEvent) {
+        let subscribers = self.state.lock().unwrap().subscribers.clone();
+        for callback in subscribers { callback(event.clone()); }
+    }
+
+    fn notify_volume(&self) {
+        let subscribers = self.state.lock().unwrap().volume_subscribers.clone();
+        for callback in subscribers { callback(); }
+    }
+}
+
+// Scripting API: building the sample setup
+impl FakeAudioBackend {
+    /// Simulate a device arriving (at 50% volume, 16 bit / 48 kHz stereo). The first device of a flow becomes
+    /// its default for every role, as Windows does when the only output / input appears.
+    pub fn add_device(&self, flow: AudioFlow, id: &str, name: &str, connection: ConnectionType, form_factor: FormFactor) {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.devices.retain(|d| d.id != id);
+            let volume = EndpointVolume { percent: 50, muted: false };
+            let format = WaveFormat { sample_rate: 48_000, channels: 2, channel_mask: 0x3, bits: 16, container_bits: 16, float: false };
+            let device = FakeDevice {
+                flow, id: id.to_string(), name: name.to_string(), connection, form_factor, state: EndpointState::Active, volume, container_id: None, format,
+            };
+            state.devices.push(device);
+            for default in state.defaults[flow_index(flow)].iter_mut().filter(|d| d.is_none()) { *default = Some(id.to_string()); }
+        }
+        self.notify(AudioDeviceEvent::Added { id: id.to_string() });
+    }
+
+    /// Put an endpoint into a physical device (endpoints sharing a container are grouped).
+    pub fn set_container_id(&self, id: &str, container_id: &str) {
+        if let Ok(device) = self.state.lock().unwrap().device_mut(id) { device.container_id = Some(container_id.to_string()); }
+    }
+}
+
+// Scripting API: changes behind the app's back, driven by unit tests
+#[cfg(test)]
+impl FakeAudioBackend {
+    /// Simulate a device disappearing; roles it held fall back to the first remaining
+    /// device of the same flow.
+    pub fn remove_device(&self, id: &str) {
+        {
+            let mut state = self.state.lock().unwrap();
+            let Some(pos) = state.devices.iter().position(|d| d.id == id) else { return };
+            let flow = state.devices.remove(pos).flow;
+            let fallback = state.devices.iter().find(|d| d.flow == flow).map(|d| d.id.clone());
+            for default in state.defaults[flow_index(flow)].iter_mut().filter(|d| d.as_deref() == Some(id)) { default.clone_from(&fallback); }
+        }
+        self.notify(AudioDeviceEvent::Removed { id: id.to_string() });
+    }
+
+    /// Simulate a jack being unplugged, a Bluetooth device disconnecting or an endpoint
+    /// being disabled (and back).
+    pub fn set_state(&self, id: &str, endpoint_state: EndpointState) {
+        if let Ok(device) = self.state.lock().unwrap().device_mut(id) { device.state = endpoint_state; }
+        self.notify(AudioDeviceEvent::StateChanged { id: id.to_string(), state: endpoint_state });
+    }
+
+    /// Simulate another application changing a default endpoint.
+    pub fn change_default_externally(&self, id: &str, role: AudioRole) {
+        let flow = {
+            let mut state = self.state.lock().unwrap();
+            let flow = state.devices.iter().find(|d| d.id == id).map_or(AudioFlow::Output, |d| d.flow);
+            state.defaults[flow_index(flow)][role_index(role)] = Some(id.to_string());
+            flow
+        };
+        self.notify(AudioDeviceEvent::DefaultChanged { flow, role, id: Some(id.to_string()) });
+    }
+
+    /// Simulate the volume mixer (or a hardware button) changing an endpoint's volume.
+    pub fn change_volume_externally(&self, id: &str, volume: EndpointVolume) {
+        if let Ok(device) = self.state.lock().unwrap().device_mut(id) { device.volume = volume; }
+        self.notify_volume();
+    }
+}
+
+impl AudioBackend for FakeAudioBackend {
+    fn enumerate(&self, flow: AudioFlow, include_inactive: bool) -> AppResult<Vec<AudioDevice>> {
+        let state = self.state.lock().unwrap();
+        let defaults = &state.defaults[flow_index(flow)];
+        let is_default_for = |role: AudioRole, id: &str| defaults[role_index(role)].as_deref() == Some(id);
+        Ok(state.devices.iter()
+            .filter(|d| d.flow == flow && (include_inactive || d.state == EndpointState::Active))
+            .map(|d| AudioDevice {
+                id: d.id.clone(),
+                name: d.name.clone(),
+                is_default: is_default_for(AudioRole::Console, &d.id),
+                is_communications_default: is_default_for(AudioRole::Communications, &d.id),
+                volume: None,
+                alias: None,
+                connection: d.connection,
+                state: d.state,
+                container_id: d.container_id.clone(),
+                form_factor: d.form_factor,
+                // Windows' friendly name is "<description> (<adapter>)"
+                description: d.name.split(" (").next().map(str::to_string),
+                formats: None,
+            })
+            .collect())
+    }
+
+    fn default_device(&self, flow: AudioFlow, role: AudioRole) -> AppResult<Option<String>> {
+        Ok(self.state.lock().unwrap().defaults[flow_index(flow)][role_index(role)].clone())
+    }
+
+    fn set_default(&self, device_id: &str, role: AudioRole) -> AppResult {
+        let flow = {
+            let mut state = self.state.lock().unwrap();
+            let flow = state.device_mut(device_id)?.flow;
+            state.defaults[flow_index(flow)][role_index(role)] = Some(device_id.to_string());
+            flow
+        };
+        self.notify(AudioDeviceEvent::DefaultChanged { flow, role, id: Some(device_id.to_string()) });
+        Ok(())
+    }
+
+    fn subscribe(&self, callback: DeviceChangeCallback) -> AppResult<Subscription> {
+        self.state.lock().unwrap().subscribers.push(callback);
+        Ok(Box::new(()))
+    }
+
+    fn volume(&self, device_id: &str) -> AppResult<EndpointVolume> {
+        Ok(self.state.lock().unwrap().device_mut(device_id)?.volume)
+    }
+
+    fn set_volume(&self, device_id: &str, percent: u8) -> AppResult {
+        self.state.lock().unwrap().device_mut(device_id)?.volume.percent = percent.min(100);
+        self.notify_volume();
+        Ok(())
+    }
+
+    fn set_muted(&self, device_id: &str, muted: bool) -> AppResult {
+        self.state.lock().unwrap().device_mut(device_id)?.volume.muted = muted;
+        self.notify_volume();
+        Ok(())
+    }
+
+    fn subscribe_volume(&self, callback: VolumeChangeCallback) -> AppResult<Subscription> {
+        self.state.lock().unwrap().volume_subscribers.push(callback);
+        Ok(Box::new(()))
+    }
+
+    fn device_formats(&self, device_id: &str) -> AppResult<Option<DeviceFormats>> {
+        let current = self.state.lock().unwrap().device_mut(device_id)?.format;
+        // Packed samples only, up to the maximum rate
+        Ok(Some(DeviceFormats::probe(current, |f| f.container_bits == f.bits && f.sample_rate <= FAKE_MAX_SAMPLE_RATE)))
+    }
+
+    fn set_device_format(&self, device_id: &str, format: &WaveFormat) -> AppResult {
+        self.state.lock().unwrap().device_mut(device_id)?.format = *format;
+        Ok(())
+    }
+
+    /// Roles a disabled device held fall back to the first active device of its flow.
+    fn set_endpoint_enabled(&self, device_id: &str, enabled: bool) -> AppResult {
+        let endpoint_state = if enabled { EndpointState::Active } else { EndpointState::Disabled };
+        {
+            let mut state = self.state.lock().unwrap();
+            let device = state.device_mut(device_id)?;
+            device.state = endpoint_state;
+            let flow = device.flow;
+            if !enabled {
+                let fallback = state.devices.iter().find(|d| d.flow == flow && d.state == EndpointState::Active).map(|d| d.id.clone());
+                for default in state.defaults[flow_index(flow)].iter_mut().filter(|d| d.as_deref() == Some(device_id)) { default.clone_from(&fallback); }
+            }
+        }
+        self.notify(AudioDeviceEvent::StateChanged { id: device_id.to_string(), state: endpoint_state });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use crate::audio_device::next_device;
+    use crate::endpoint_group::group_endpoints;
+
+    const SPEAKERS: &str = "{fake}.speakers";
+    const HEADPHONES: &str = "{fake}.headphones";
+    const HEADSET: &str = "{fake}.headset";
+
+    fn recorded_events(fake: &FakeAudioBackend) -> Arc<Mutex<Vec<AudioDeviceEvent>>> {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&events);
+        let _subscription = fake.subscribe(Arc::new(move |event| sink.lock().unwrap().push(event))).unwrap();
+        events
+    }
+
+    fn default_output(fake: &FakeAudioBackend, role: AudioRole) -> Option<String> {
+        fake.default_device(AudioFlow::Output, role).unwrap()
+    }
+
+    #[test]
+    fn first_device_of_a_flow_becomes_default() {
+        let fake = FakeAudioBackend::with_sample_devices();
+        for role in AudioRole::ALL { assert_eq!(default_output(&fake, role).as_deref(), Some(SPEAKERS)); }
+        assert_eq!(fake.default_device(AudioFlow::Input, AudioRole::Console).unwrap().as_deref(), Some("{fake}.microphone"));
+    }
+
+    #[test]
+    fn removing_the_default_falls_back_to_the_first_remaining_device() {
+        let fake = FakeAudioBackend::with_sample_devices();
+        fake.set_default_all_roles(HEADPHONES).unwrap();
+        let events = recorded_events(&fake);
+        fake.remove_device(HEADPHONES);
+        assert_eq!(*events.lock().unwrap(), vec![AudioDeviceEvent::Removed { id: HEADPHONES.to_string() }]);
+        for role in AudioRole::ALL { assert_eq!(default_output(&fake, role).as_deref(), Some(SPEAKERS)); }
+        fake.add_device(AudioFlow::Output, HEADPHONES, "Headphones (Fake Headset)", ConnectionType::Bluetooth, FormFactor::Headphones);
+        assert_eq!(default_output(&fake, AudioRole::Console).as_deref(), Some(SPEAKERS));
+    }
+
+    #[test]
+    fn external_default_change_notifies_subscribers() {
+        let fake = FakeAudioBackend::with_sample_devices();
+        let events = recorded_events(&fake);
+        fake.change_default_externally(HEADSET, AudioRole::Communications);
+        let expected = AudioDeviceEvent::DefaultChanged { flow: AudioFlow::Output, role: AudioRole::Communications, id: Some(HEADSET.to_string()) };
+        assert_eq!(*events.lock().unwrap(), vec![expected]);
+        let devices = fake.enumerate(AudioFlow::Output, false).unwrap();
+        let speakers = devices.iter().find(|d| d.id == SPEAKERS).unwrap();
+        let headset = devices.iter().find(|d| d.id == HEADSET).unwrap();
+        assert!(speakers.is_default && !speakers.is_communications_default);
+        assert!(headset.is_communications_default && !headset.is_default);
+    }
+
+    #[test]
+    fn selecting_a_grouped_headset_splits_roles() {
+        let fake = FakeAudioBackend::with_sample_devices();
+        let devices = fake.enumerate(AudioFlow::Output, false).unwrap();
+        let groups = group_endpoints(&devices);
+        assert_eq!(groups.len(), 2);
+        let headset = groups.iter().find(|g| g.is_group()).unwrap();
+        for (device, roles) in headset.assignments(&devices) { fake.set_default_roles(&device.id, roles).unwrap(); }
+
+        assert_eq!(default_output(&fake, AudioRole::Multimedia).as_deref(), Some(HEADPHONES));
+        assert_eq!(default_output(&fake, AudioRole::Communications).as_deref(), Some(HEADSET));
+        let devices = fake.enumerate(AudioFlow::Output, false).unwrap();
+        let entry = headset.entry(&devices);
+        assert!(entry.is_default && entry.is_communications_default);
+        assert!(!groups.iter().find(|g| !g.is_group()).unwrap().entry(&devices).is_default);
+    }
+
+    #[test]
+    fn cycling_skips_unplugged_devices_and_wraps() {
+        let fake = FakeAudioBackend::with_sample_devices();
+        fake.set_state(HEADPHONES, EndpointState::Unplugged);
+        let mut visited = Vec::new();
+        for _ in 0..3 {
+            let devices = fake.enumerate(AudioFlow::Output, true).unwrap();
+            let next = next_device(&devices).unwrap();
+            fake.set_default_all_roles(&next.id).unwrap();
+            visited.push(next.id.clone());
+        }
+        assert_eq!(visited, [HEADSET, SPEAKERS, HEADSET]);
+    }
+
+    #[test]
+    fn disabling_the_default_falls_back_to_an_active_device() {
+        let fake = FakeAudioBackend::with_sample_devices();
+        fake.set_state(HEADPHONES, EndpointState::Unplugged);
+        fake.set_endpoint_enabled(SPEAKERS, false).unwrap();
+        assert_eq!(default_output(&fake, AudioRole::Console).as_deref(), Some(HEADSET));
+        assert!(fake.enumerate(AudioFlow::Output, false).unwrap().iter().all(|d| d.id == HEADSET));
+        assert_eq!(fake.enumerate(AudioFlow::Output, true).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn external_volume_change_notifies_volume_subscribers() {
+        let fake = FakeAudioBackend::with_sample_devices();
+        let calls = Arc::new(Mutex::new(0));
+        let sink = Arc::clone(&calls);
+        let _subscription = fake.subscribe_volume(Arc::new(move || *sink.lock().unwrap() += 1)).unwrap();
+        let muted = EndpointVolume { percent: 30, muted: true };
+        fake.change_volume_externally(SPEAKERS, muted);
+        assert_eq!(*calls.lock().unwrap(), 1);
+        assert_eq!(fake.volume(SPEAKERS).unwrap(), muted);
+    }
+}
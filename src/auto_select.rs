@@ -0,0 +1,107 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2025 Triffit
+
+//! Priority-list auto-selection of the default output (`output_priority`,
+//! e.g. `WH-1000XM4 > USB DAC > Speakers`). Each entry matches an endpoint ID
+//! or, case-insensitively, part of a device's name, alias or description. Selection is a pure
+//! function of the device list; main runs it after every audio refresh unless
+//! the user picked an output since the last device change.
+
+use crate::audio_device::AudioDevice;
+
+/// Parsed `output_priority`: matchers, highest rank first.
+#[derive(Debug, Clone, Default)]
+pub struct OutputPriority {
+    rules: Vec<String>, // lowercase
+}
+
+impl OutputPriority {
+    pub fn parse(text: &str) -> Self {
+        let rules = text.split('>').map(str::trim).filter(|r| !r.is_empty()).map(str::to_lowercase).collect();
+        Self { rules }
+    }
+
+    pub fn is_empty(&self) -> bool { self.rules.is_empty() }
+
+    /// Index of the first entry matching the device (lower = preferred).
+    fn rank(&self, device: &AudioDevice) -> Option<usize> {
+        let name = device.name.to_lowercase();
+        let display_name = device.display_name().to_lowercase();
+        let description = device.description.as_deref().map(str::to_lowercase).unwrap_or_default();
+        self.rules.iter().position(|rule| {
+            device.id.eq_ignore_ascii_case(rule) || name.contains(rule) || display_name.contains(rule) || description.contains(rule)
+        })
+    }
+
+    /// Output to switch to: the highest-ranked active device (first listed on ties);
+    /// None when it already is the default or no active device is ranked.
+    pub fn select<'a>(&self, devices: &'a [AudioDevice]) -> Option<&'a AudioDevice> {
+        let (_, best) = devices.iter()
+            .filter(|d| d.is_active())
+            .filter_map(|d| Some((self.rank(d)?, d)))
+            .min_by_key(|&(rank, _)| rank)?;
+        (!best.is_default).then_some(best)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio_device::EndpointState;
+
+    fn selected<'a>(priority: &OutputPriority, devices: &'a [AudioDevice]) -> Option<&'a str> {
+        priority.select(devices).map(|d| d.id.as_str())
+    }
+
+    #[test]
+    fn parse_skips_empty_entries() {
+        let priority = OutputPriority::parse("A > B >  > C");
+        assert_eq!(priority.rules, ["a", "b", "c"]);
+        assert!(OutputPriority::parse(" > ").is_empty());
+    }
+
+    #[test]
+    fn matches_id_name_alias_and_description() {
+        let devices = [
+            AudioDevice::sample("{0.0.0.00000000}.{aaaa}", "Speakers (Realtek Audio)"),
+            AudioDevice { alias: Some("Desk DAC".to_string()), ..AudioDevice::sample("dac", "Speakers (USB Audio)") },
+            AudioDevice { description: Some("Headphones".to_string()), ..AudioDevice::sample("bt", "WH-1000XM4") },
+        ];
+        assert_eq!(selected(&OutputPriority::parse("{0.0.0.00000000}.{AAAA}"), &devices), Some("{0.0.0.00000000}.{aaaa}"));
+        assert_eq!(selected(&OutputPriority::parse("realtek"), &devices), Some("{0.0.0.00000000}.{aaaa}"));
+        assert_eq!(selected(&OutputPriority::parse("desk dac"), &devices), Some("dac"));
+        assert_eq!(selected(&OutputPriority::parse("HEADPHONES"), &devices), Some("bt"));
+        // IDs match whole, names in part
+        assert_eq!(selected(&OutputPriority::parse("{0.0.0.00000000}"), &devices), None);
+        assert_eq!(selected(&OutputPriority::parse("Nothing"), &devices), None);
+    }
+
+    #[test]
+    fn inactive_devices_are_skipped() {
+        let devices = [
+            AudioDevice { state: EndpointState::Unplugged, ..AudioDevice::sample("bt", "WH-1000XM4") },
+            AudioDevice::sample("spk", "Speakers"),
+        ];
+        assert_eq!(selected(&OutputPriority::parse("WH-1000XM4 > Speakers"), &devices), Some("spk"));
+    }
+
+    #[test]
+    fn higher_rank_wins_and_ties_follow_list_order() {
+        let devices = [
+            AudioDevice::sample("usb", "Speakers (USB DAC)"),
+            AudioDevice::sample("hdmi", "Speakers (HDMI)"),
+            AudioDevice::sample("bt", "WH-1000XM4"),
+        ];
+        assert_eq!(selected(&OutputPriority::parse("WH-1000XM4 > Speakers"), &devices), Some("bt"));
+        assert_eq!(selected(&OutputPriority::parse("Speakers > WH-1000XM4"), &devices), Some("usb"));
+    }
+
+    #[test]
+    fn nothing_to_do_when_the_best_device_is_default() {
+        let devices = [
+            AudioDevice { is_default: true, ..AudioDevice::sample("bt", "WH-1000XM4") },
+            AudioDevice::sample("spk", "Speakers"),
+        ];
+        assert_eq!(selected(&OutputPriority::parse("WH-1000XM4 > Speakers"), &devices), None);
+    }
+}
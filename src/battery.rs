@@ -0,0 +1,106 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2025 Triffit
+
+//! Bluetooth battery levels from the PnP device property store.
+//! Windows publishes `DEVPKEY_Bluetooth_Battery` (0-100) on the device nodes of
+//! headsets that report battery via the GATT battery service (BTHLE) or the HFP
+//! AT+IPHONEACCEV indicator (BTHENUM). Nodes are matched to paired devices by
+//! the Bluetooth address embedded in the instance ID.
+
+use windows_sys::core::GUID;
+use windows_sys::Win32::Foundation::INVALID_HANDLE_VALUE;
+use windows_sys::Win32::Devices::DeviceAndDriverInstallation::{
+    SetupDiGetClassDevsW, SetupDiEnumDeviceInfo, SetupDiGetDeviceInstanceIdW,
+    SetupDiGetDevicePropertyW, SetupDiDestroyDeviceInfoList, SP_DEVINFO_DATA,
+    DIGCF_ALLCLASSES, DIGCF_PRESENT,
+};
+use windows_sys::Win32::Devices::Properties::{DEVPROPKEY, DEVPROPTYPE, DEVPROP_TYPE_BYTE};
+use crate::bluetooth::BluetoothDevice;
+use crate::log_dbg;
+use crate::utf16_strings::encode_utf16_null;
+
+#[allow(non_upper_case_globals)]
+const DEVPKEY_Bluetooth_Battery: DEVPROPKEY = DEVPROPKEY {
+    fmtid: GUID::from_u128(0x104ea319_6ee2_4701_bd47_8ddbf425bbe5),
+    pid: 2,
+};
+
+// Device enumerators carrying Bluetooth device nodes (Classic and LE).
+const BT_ENUMERATORS: [&str; 2] = ["BTHENUM", "BTHLE"];
+
+/// Read battery percentages for the given device addresses.
+/// Returns `(address, percent)` for every address with a reported level.
+pub fn read_battery_levels(addresses: &[u64]) -> Vec<(u64, u8)> {
+    let mut levels: Vec<(u64, u8)> = Vec::new();
+    if addresses.is_empty() { return levels; }
+    let needles: Vec<(u64, String)> = addresses.iter().map(|&a| (a, format!("{a:012X}"))).collect();
+    for enumerator in BT_ENUMERATORS {
+        let enumerator = encode_utf16_null(enumerator);
+        unsafe {
+            let devs = SetupDiGetClassDevsW(std::ptr::null(), enumerator.as_ptr(), std::ptr::null_mut(), DIGCF_ALLCLASSES | DIGCF_PRESENT);
+            if devs == INVALID_HANDLE_VALUE { continue; }
+            let mut index = 0;
+            loop {
+                let mut data: SP_DEVINFO_DATA = std::mem::zeroed();
+                data.cbSize = std::mem::size_of::<SP_DEVINFO_DATA>() as u32;
+                if SetupDiEnumDeviceInfo(devs, index, &mut data) == 0 { break; }
+                index += 1;
+
+                let mut prop_type: DEVPROPTYPE = 0;
+                let mut level: u8 = 0;
+                let ok = SetupDiGetDevicePropertyW(
+                    devs, &data, &DEVPKEY_Bluetooth_Battery, &mut prop_type,
+                    &mut level, 1, std::ptr::null_mut(), 0,
+                );
+                if ok == 0 || prop_type != DEVPROP_TYPE_BYTE { continue; }
+
+                let mut id_buf = [0u16; 512];
+                if SetupDiGetDeviceInstanceIdW(devs, &data, id_buf.as_mut_ptr(), id_buf.len() as u32, std::ptr::null_mut()) == 0 { continue; }
+                let len = id_buf.iter().position(|&c| c == 0).unwrap_or(id_buf.len());
+                let instance_id = String::from_utf16_lossy(&id_buf[..len]).to_uppercase();
+
+                if let Some((address, _)) = needles.iter().find(|(_, hex)| instance_id.contains(hex.as_str())) {
+                    // Several nodes per device may report; keep the first.
+                    if !levels.iter().any(|(a, _)| a == address) { levels.push((*address, level.min(100))); }
+                }
+            }
+            SetupDiDestroyDeviceInfoList(devs);
+        }
+    }
+    log_dbg!("battery: {} device(s) reporting battery level", levels.len());
+    levels
+}
+
+/// True if a fresh read differs from the levels attached to `devices`.
+pub fn levels_changed(devices: &[BluetoothDevice]) -> bool {
+    let connected: Vec<u64> = devices.iter().filter(|d| d.connected).map(|d| d.address).collect();
+    let fresh = read_battery_levels(&connected);
+    devices.iter().filter(|d| d.connected).any(|d| {
+        d.battery != fresh.iter().find(|(a, _)| *a == d.address).map(|&(_, l)| l)
+    })
+}
+
+/// Tracks which devices were already warned so each low-battery episode
+/// produces a single notification (re-armed once the level recovers).
+#[derive(Debug, Default)]
+pub struct LowBatteryWatch {
+    warned: Vec<u64>,
+}
+
+impl LowBatteryWatch {
+    /// Devices that just dropped to or below `threshold` percent.
+    pub fn check<'a>(&mut self, devices: &'a [BluetoothDevice], threshold: u8) -> Vec<&'a BluetoothDevice> {
+        let mut newly_low = Vec::new();
+        for device in devices {
+            let low = device.connected && device.battery.is_some_and(|l| l <= threshold);
+            let already = self.warned.contains(&device.address);
+            if low && !already {
+                self.warned.push(device.address);
+                newly_low.push(device);
+            } else if !low && already {
+                self.warned.retain(|&a| a != device.address);
+            }
+        }
+        newly_low
+    }
+}
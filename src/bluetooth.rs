@@ -1,9 +1,20 @@
 // SPDX-License-Identifier: MIT
 // Copyright (c) 2025 Triffit
+use windows_sys::core::GUID;
+use windows_sys::Win32::Foundation::{HANDLE, CloseHandle};
+use windows_sys::Win32::Devices::Bluetooth::{
+    BluetoothFindFirstRadio, BluetoothFindRadioClose, BLUETOOTH_FIND_RADIO_PARAMS,
+    BluetoothFindFirstDevice, BluetoothFindNextDevice, BluetoothFindDeviceClose,
+    BLUETOOTH_DEVICE_SEARCH_PARAMS, BLUETOOTH_DEVICE_INFO, BluetoothGetDeviceInfo,
+    BluetoothSetServiceState, BLUETOOTH_SERVICE_ENABLE, BLUETOOTH_SERVICE_DISABLE,
+    BluetoothAuthenticateDeviceEx, MITMProtectionNotRequired, BluetoothRemoveDevice, BLUETOOTH_ADDRESS,
+};
 use windows_sys::Win32::UI::Shell::ShellExecuteW;
 use windows_sys::Win32::UI::WindowsAndMessaging::SW_SHOW;
 use std::ptr;
 use crate::log_dbg;
+use crate::battery;
+use crate::config::{AppResult, DISCOVERY_INQUIRY_MULTIPLIER};
 use crate::utf16_strings::{encode_utf16_null, UTF16_OPEN};
 
 pub fn show_bluetooth_ui() -> bool {
@@ -14,6 +25,13 @@ pub fn show_bluetooth_ui() -> bool {
     launch_uri("ms-settings:bluetooth")
 }
 
+/// Windows "Add a device" wizard (Settings page, legacy wizard as fallback).
+pub fn show_pairing_wizard() -> bool {
+    if launch_uri("ms-settings-connectabledevices:devicediscovery") { return true; }
+    log_dbg!("bluetooth: device discovery URI failed; falling back to DevicePairingWizard");
+    launch_uri("DevicePairingWizard.exe")
+}
+
 fn launch_uri(uri: &str) -> bool {
     let operation = UTF16_OPEN;
     let file = encode_utf16_null(uri);
@@ -38,3 +56,176 @@ fn launch_uri(uri: &str) -> bool {
     }
 }
 
+// ═══════════════════════════════════════════════════════════════════════════════
+// Paired device enumeration & connect / disconnect (Win32 Bluetooth API)
+// ═══════════════════════════════════════════════════════════════════════════════
+
+// Service class UUIDs (Bluetooth base UUID 0000xxxx-0000-1000-8000-00805F9B34FB)
+const AUDIO_SINK_SERVICE: GUID = GUID::from_u128(0x0000110b_0000_1000_8000_00805f9b34fb); // A2DP
+const HANDSFREE_SERVICE: GUID = GUID::from_u128(0x0000111e_0000_1000_8000_00805f9b34fb); // HFP
+const HEADSET_SERVICE: GUID = GUID::from_u128(0x00001108_0000_1000_8000_00805f9b34fb); // HSP
+
+// Class of Device: major class bits 8..12, 0x04 = Audio/Video.
+const COD_MAJOR_AUDIO: u32 = 0x04;
+
+/// Paired (remembered) Bluetooth device.
+#[derive(Debug, Clone)]
+pub struct BluetoothDevice {
+    pub address: u64,
+    pub name: String,
+    pub connected: bool,
+    pub class_of_device: u32,
+    pub battery: Option<u8>, // percent, connected devices that report it
+}
+
+impl BluetoothDevice {
+    /// Headsets, headphones and speakers (connect via A2DP/HFP service toggling).
+    pub fn is_audio(&self) -> bool { (self.class_of_device >> 8) & 0x1F == COD_MAJOR_AUDIO }
+
+    /// Menu / tooltip label suffix, e.g. " (80%)".
+    pub fn battery_suffix(&self) -> String {
+        self.battery.map(|l| format!(" ({l}%)")).unwrap_or_default()
+    }
+}
+
+/// Paired device an audio endpoint belongs to, matched by friendly name
+/// ("Headphones (WH-1000XM4)" contains "WH-1000XM4").
+pub fn device_for_endpoint<'a>(endpoint_name: &str, devices: &'a [BluetoothDevice]) -> Option<&'a BluetoothDevice> {
+    let endpoint_name = endpoint_name.to_lowercase();
+    devices.iter().find(|d| endpoint_name.contains(&d.name.to_lowercase()))
+}
+
+/// Owned handle to the first local Bluetooth radio (closed on drop).
+pub struct Radio(HANDLE);
+
+impl Radio {
+    pub fn first() -> AppResult<Self> {
+        let params = BLUETOOTH_FIND_RADIO_PARAMS { dwSize: std::mem::size_of::<BLUETOOTH_FIND_RADIO_PARAMS>() as u32 };
+        let mut radio: HANDLE = ptr::null_mut();
+        let find = unsafe { BluetoothFindFirstRadio(&params, &mut radio) };
+        if find.is_null() { return Err("No Bluetooth radio found".into()); }
+        unsafe { BluetoothFindRadioClose(find); }
+        Ok(Self(radio))
+    }
+
+    pub fn handle(&self) -> HANDLE { self.0 }
+}
+
+impl Drop for Radio {
+    fn drop(&mut self) { unsafe { CloseHandle(self.0); } }
+}
+
+fn empty_device_info() -> BLUETOOTH_DEVICE_INFO {
+    let mut info: BLUETOOTH_DEVICE_INFO = unsafe { std::mem::zeroed() };
+    info.dwSize = std::mem::size_of::<BLUETOOTH_DEVICE_INFO>() as u32;
+    info
+}
+
+fn device_from_info(info: &BLUETOOTH_DEVICE_INFO) -> BluetoothDevice {
+    let len = info.szName.iter().position(|&c| c == 0).unwrap_or(info.szName.len());
+    let address = unsafe { info.Address.Anonymous.ullLong };
+    let name = String::from_utf16_lossy(&info.szName[..len]);
+    BluetoothDevice {
+        address,
+        name: if name.is_empty() { format!("{address:012X}") } else { name },
+        connected: info.fConnected != 0,
+        class_of_device: info.ulClassofDevice,
+        battery: None,
+    }
+}
+
+/// Run a device search on `radio`, keeping entries accepted by `keep`.
+fn search_devices(radio: &Radio, inquiry: bool, keep: fn(&BLUETOOTH_DEVICE_INFO) -> bool) -> Vec<BluetoothDevice> {
+    let params = BLUETOOTH_DEVICE_SEARCH_PARAMS {
+        dwSize: std::mem::size_of::<BLUETOOTH_DEVICE_SEARCH_PARAMS>() as u32,
+        fReturnAuthenticated: 1,
+        fReturnRemembered: 1,
+        fReturnUnknown: i32::from(inquiry),
+        fReturnConnected: 1,
+        fIssueInquiry: i32::from(inquiry),
+        cTimeoutMultiplier: if inquiry { DISCOVERY_INQUIRY_MULTIPLIER } else { 0 },
+        hRadio: radio.0,
+    };
+    let mut info = empty_device_info();
+    let mut devices = Vec::new();
+    unsafe {
+        let find = BluetoothFindFirstDevice(&params, &mut info);
+        if find.is_null() { return devices; }
+        loop {
+            if keep(&info) { devices.push(device_from_info(&info)); }
+            info = empty_device_info();
+            if BluetoothFindNextDevice(find, &mut info) == 0 { break; }
+        }
+        BluetoothFindDeviceClose(find);
+    }
+    devices
+}
+
+/// Enumerate paired Bluetooth devices (no inquiry; cached state only, returns quickly).
+pub fn enumerate_paired_devices() -> AppResult<Vec<BluetoothDevice>> {
+    let radio = Radio::first()?;
+    let mut devices = search_devices(&radio, false, |info| info.fRemembered != 0);
+    let connected: Vec<u64> = devices.iter().filter(|d| d.connected).map(|d| d.address).collect();
+    for (address, level) in battery::read_battery_levels(&connected) {
+        if let Some(device) = devices.iter_mut().find(|d| d.address == address) { device.battery = Some(level); }
+    }
+    log_dbg!("bluetooth: enumerated {} paired device(s)", devices.len());
+    Ok(devices)
+}
+
+/// Inquiry for nearby, not yet paired devices. Blocks for the inquiry
+/// duration (~10 s), so call it off the UI thread.
+pub fn discover_devices() -> AppResult<Vec<BluetoothDevice>> {
+    let radio = Radio::first()?;
+    let devices = search_devices(&radio, true, |info| info.fRemembered == 0 && info.fAuthenticated == 0);
+    log_dbg!("bluetooth: discovered {} nearby device(s)", devices.len());
+    Ok(devices)
+}
+
+fn device_info(radio: &Radio, address: u64) -> AppResult<BLUETOOTH_DEVICE_INFO> {
+    let mut info = empty_device_info();
+    info.Address.Anonymous.ullLong = address;
+    let err = unsafe { BluetoothGetDeviceInfo(radio.0, &mut info) };
+    if err != 0 { return Err(format!("Bluetooth device {address:012X} not found (error {err})").into()); }
+    Ok(info)
+}
+
+/// Pair with a discovered device. Windows shows its own PIN / confirmation
+/// prompt when the device requires one; blocks until pairing completes.
+pub fn pair_device(address: u64) -> AppResult {
+    let radio = Radio::first()?;
+    let mut info = device_info(&radio, address)?;
+    let err = unsafe {
+        BluetoothAuthenticateDeviceEx(ptr::null_mut(), radio.0, &mut info, ptr::null(), MITMProtectionNotRequired)
+    };
+    if err != 0 { return Err(format!("Pairing with {address:012X} failed (error {err})").into()); }
+    log_dbg!("bluetooth: paired {address:012X}");
+    Ok(())
+}
+
+/// Remove (unpair) a device.
+pub fn remove_device(address: u64) -> AppResult {
+    let mut bt_address: BLUETOOTH_ADDRESS = unsafe { std::mem::zeroed() };
+    bt_address.Anonymous.ullLong = address;
+    let err = unsafe { BluetoothRemoveDevice(&bt_address) };
+    if err != 0 { return Err(format!("Removing {address:012X} failed (error {err})").into()); }
+    log_dbg!("bluetooth: removed {address:012X}");
+    Ok(())
+}
+
+/// Connect or disconnect a paired audio device by enabling / disabling its
+/// A2DP and hands-free services. Blocks while the stack (re)installs the
+/// service drivers, so call it off the UI thread.
+pub fn set_device_connected(address: u64, connect: bool) -> AppResult {
+    let radio = Radio::first()?;
+    let info = device_info(&radio, address)?;
+    let flags = if connect { BLUETOOTH_SERVICE_ENABLE } else { BLUETOOTH_SERVICE_DISABLE };
+    let mut toggled = 0;
+    for service in [AUDIO_SINK_SERVICE, HANDSFREE_SERVICE, HEADSET_SERVICE] {
+        let err = unsafe { BluetoothSetServiceState(radio.0, &info, &service, flags) };
+        if err == 0 { toggled += 1; } else { log_dbg!("bluetooth: service {:08x} state change failed ({err})", service.data1); }
+    }
+    if toggled == 0 { return Err(format!("No audio service could be {} on {address:012X}", if connect { "enabled" } else { "disabled" }).into()); }
+    log_dbg!("bluetooth: {} {address:012X} ({toggled} service(s))", if connect { "connected" } else { "disconnected" });
+    Ok(())
+}
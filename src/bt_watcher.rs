@@ -0,0 +1,99 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2025 Triffit
+
+//! Event-driven Bluetooth connection watcher.
+//! Registers for custom device events on the local radio handle via
+//! `CM_Register_Notification`; the Bluetooth stack raises
+//! `GUID_BLUETOOTH_HCI_EVENT` with the remote address whenever a link comes up
+//! or goes down, so no polling is needed (counterpart of `DeviceNotificationClient`).
+
+use std::ffi::c_void;
+use std::sync::Arc;
+use windows_sys::core::GUID;
+use windows_sys::Win32::Devices::DeviceAndDriverInstallation::{
+    CM_Register_Notification, CM_Unregister_Notification, CM_NOTIFY_FILTER, CM_NOTIFY_EVENT_DATA,
+    CM_NOTIFY_ACTION, CM_NOTIFY_ACTION_DEVICECUSTOMEVENT, CM_NOTIFY_FILTER_TYPE_DEVICEHANDLE,
+    HCMNOTIFICATION, CR_SUCCESS,
+};
+use crate::bluetooth::Radio;
+use crate::config::AppResult;
+use crate::log_dbg;
+
+// {FC240062-1541-49BE-B463-84C4DCD7BF7F} (bthdef.h)
+const GUID_BLUETOOTH_HCI_EVENT: GUID = GUID::from_u128(0xfc240062_1541_49be_b463_84c4dcd7bf7f);
+
+// HCI link types (bthdef.h); SCO links are call audio channels, not device connections.
+const HCI_CONNECTION_TYPE_SCO: u8 = 2;
+
+/// Payload of `GUID_BLUETOOTH_HCI_EVENT` (manually defined, see bthdef.h).
+#[repr(C)]
+#[allow(non_snake_case)]
+struct BTH_HCI_EVENT_INFO {
+    bthAddress: u64,
+    connectionType: u8,
+    connected: u8,
+}
+
+/// Callback receiving `(device address, connected)`.
+pub type ConnectionCallback = Arc<dyn Fn(u64, bool) + Send + Sync>;
+
+/// Keeps the registration (and the radio handle it targets) alive; unregisters on drop.
+pub struct WatcherGuard {
+    notification: HCMNOTIFICATION,
+    context: *mut ConnectionCallback,
+    _radio: Radio,
+}
+
+impl Drop for WatcherGuard {
+    fn drop(&mut self) {
+        unsafe {
+            // Blocks until in-flight callbacks return, so the context can be freed afterwards.
+            CM_Unregister_Notification(self.notification);
+            drop(Box::from_raw(self.context));
+        }
+        log_dbg!("bt-watcher: unregistered");
+    }
+}
+
+fn guid_eq(a: &GUID, b: &GUID) -> bool {
+    a.data1 == b.data1 && a.data2 == b.data2 && a.data3 == b.data3 && a.data4 == b.data4
+}
+
+unsafe extern "system" fn on_notification(
+    _notification: HCMNOTIFICATION,
+    context: *const c_void,
+    action: CM_NOTIFY_ACTION,
+    event_data: *const CM_NOTIFY_EVENT_DATA,
+    _event_data_size: u32,
+) -> u32 {
+    if action != CM_NOTIFY_ACTION_DEVICECUSTOMEVENT || event_data.is_null() { return 0; }
+    let custom = &(*event_data).u.DeviceHandle;
+    if !guid_eq(&custom.EventGuid, &GUID_BLUETOOTH_HCI_EVENT) { return 0; }
+    if (custom.DataSize as usize) < std::mem::size_of::<BTH_HCI_EVENT_INFO>() { return 0; }
+    let info = std::ptr::read_unaligned(custom.Data.as_ptr().cast::<BTH_HCI_EVENT_INFO>());
+    if info.connectionType == HCI_CONNECTION_TYPE_SCO { return 0; }
+    log_dbg!("bt-watcher: {:012X} connected={}", info.bthAddress, info.connected != 0);
+    let callback = &*context.cast::<ConnectionCallback>();
+    callback(info.bthAddress, info.connected != 0);
+    0
+}
+
+/// Start watching connect / disconnect events on the first local radio.
+/// The returned guard must be kept alive to continue receiving events.
+pub fn register_connection_watcher(callback: ConnectionCallback) -> AppResult<WatcherGuard> {
+    let radio = Radio::first()?;
+    let mut filter: CM_NOTIFY_FILTER = unsafe { std::mem::zeroed() };
+    filter.cbSize = std::mem::size_of::<CM_NOTIFY_FILTER>() as u32;
+    filter.FilterType = CM_NOTIFY_FILTER_TYPE_DEVICEHANDLE;
+    filter.u.DeviceHandle.hTarget = radio.handle();
+
+    let context = Box::into_raw(Box::new(callback));
+    let mut notification: HCMNOTIFICATION = std::ptr::null_mut();
+    let cr = unsafe { CM_Register_Notification(&filter, context as *const c_void, Some(on_notification), &mut notification) };
+    if cr != CR_SUCCESS {
+        unsafe { drop(Box::from_raw(context)); }
+        return Err(format!("Failed to register Bluetooth connection notifications (CONFIGRET {cr})").into());
+    }
+    log_dbg!("bt-watcher: registered on local radio");
+    Ok(WatcherGuard { notification, context, _radio: radio })
+}
@@ -0,0 +1,103 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2025 Triffit
+
+//! Coalescing of notification bursts (e.g. the state / property / default
+//! changes Windows fires while a Bluetooth headset connects) into one refresh.
+//! Pure state machine driven by explicit `now` values, like `connect_flow`:
+//! the refresh is due once no event arrived for the quiet period, but never
+//! later than `max_delay` after the first event of the burst.
+
+use std::time::{Duration, Instant};
+
+#[derive(Debug)]
+pub struct Coalescer {
+    quiet: Duration,
+    max_delay: Duration,
+    /// (first event of the pending burst, refresh due at)
+    pending: Option<(Instant, Instant)>,
+}
+
+impl Coalescer {
+    pub fn new(quiet: Duration, max_delay: Duration) -> Self {
+        Self { quiet, max_delay, pending: None }
+    }
+
+    /// Record an event; pushes the refresh back by the quiet period.
+    pub fn on_event(&mut self, now: Instant) {
+        let first = self.pending.map_or(now, |(first, _)| first);
+        let due = (now + self.quiet).min(first + self.max_delay.max(self.quiet));
+        self.pending = Some((first, due));
+    }
+
+    /// When the pending refresh is due (None = nothing pending).
+    pub fn deadline(&self) -> Option<Instant> { self.pending.map(|(_, due)| due) }
+
+    /// True exactly once per burst, when its refresh is due.
+    pub fn take_due(&mut self, now: Instant) -> bool {
+        match self.pending {
+            Some((_, due)) if now >= due => { self.pending = None; true }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUIET: Duration = Duration::from_millis(250);
+    const MAX_DELAY: Duration = Duration::from_secs(2);
+    const MS: Duration = Duration::from_millis(1);
+
+    #[test]
+    fn events_push_the_deadline_back() {
+        let start = Instant::now();
+        let mut coalescer = Coalescer::new(QUIET, MAX_DELAY);
+        assert_eq!(coalescer.deadline(), None);
+        coalescer.on_event(start);
+        assert_eq!(coalescer.deadline(), Some(start + QUIET));
+        let later = start + Duration::from_millis(100);
+        coalescer.on_event(later);
+        assert_eq!(coalescer.deadline(), Some(later + QUIET));
+        assert!(!coalescer.take_due(start + QUIET));
+        assert!(coalescer.take_due(later + QUIET));
+    }
+
+    #[test]
+    fn continuous_burst_is_capped_at_max_delay() {
+        let start = Instant::now();
+        let mut coalescer = Coalescer::new(QUIET, MAX_DELAY);
+        let mut now = start;
+        while now < start + MAX_DELAY * 2 {
+            coalescer.on_event(now);
+            assert!(coalescer.deadline().unwrap() <= start + MAX_DELAY);
+            if coalescer.take_due(now) { break; }
+            now += Duration::from_millis(100);
+        }
+        assert_eq!(now, start + MAX_DELAY);
+        // The next event starts a new burst
+        coalescer.on_event(now + MS);
+        assert_eq!(coalescer.deadline(), Some(now + MS + QUIET));
+    }
+
+    #[test]
+    fn max_delay_never_shortens_the_quiet_period() {
+        let start = Instant::now();
+        let mut coalescer = Coalescer::new(QUIET, MS);
+        coalescer.on_event(start);
+        assert_eq!(coalescer.deadline(), Some(start + QUIET));
+    }
+
+    #[test]
+    fn take_due_fires_once_per_burst() {
+        let start = Instant::now();
+        let mut coalescer = Coalescer::new(QUIET, MAX_DELAY);
+        assert!(!coalescer.take_due(start));
+        coalescer.on_event(start);
+        assert!(!coalescer.take_due(start + QUIET - MS));
+        assert!(coalescer.take_due(start + QUIET));
+        assert!(!coalescer.take_due(start + QUIET));
+        assert!(!coalescer.take_due(start + MAX_DELAY * 10));
+        assert_eq!(coalescer.deadline(), None);
+    }
+}
@@ -11,3 +11,31 @@ pub type AppResult<T = ()> = Result<T, Box<dyn std::error::Error>>;
 pub const CLICK_DEBOUNCE: Duration = Duration::from_millis(250);
 pub const TOGGLE_MIN_HIDE: Duration = Duration::from_millis(800);
 pub const PASS_THROUGH_WINDOW_MS: u64 = 1200; // Win+K pass-through window for second press
+
+// Connect & use orchestration
+pub const BT_CONNECT_TIMEOUT: Duration = Duration::from_secs(20);
+pub const ENDPOINT_ARRIVAL_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Battery monitoring
+pub const BATTERY_REFRESH_INTERVAL: Duration = Duration::from_secs(300);
+pub const BALLOON_DURATION: Duration = Duration::from_secs(10);
+
+// Nearby device discovery: inquiry length in units of 1.28 s (~10 s)
+pub const DISCOVERY_INQUIRY_MULTIPLIER: u8 = 8;
+
+// Reconnect after resume: settle delay before the first attempt, then exponential backoff
+pub const RESUME_SETTLE_DELAY: Duration = Duration::from_secs(5);
+pub const RESUME_RETRY_BASE_DELAY: Duration = Duration::from_secs(5);
+pub const RESUME_RETRY_MAX_DELAY: Duration = Duration::from_secs(60);
+pub const DEFAULT_RESUME_RECONNECT_ATTEMPTS: u32 = 3;
+
+// Audio notification bursts: refresh once events pause this long (settings override), at most after the max delay
+pub const DEFAULT_AUDIO_REFRESH_QUIET: Duration = Duration::from_millis(300);
+pub const AUDIO_REFRESH_MAX_DELAY: Duration = Duration::from_secs(2);
+
+// Default-device lock: more reverts than this within the window release the lock
+pub const MAX_LOCK_REVERTS: usize = 5;
+pub const LOCK_REVERT_WINDOW: Duration = Duration::from_secs(60);
+
+// Per-device volume: step for the tray's up / down actions
+pub const VOLUME_STEP_PERCENT: u8 = 10;
@@ -0,0 +1,209 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2025 Triffit
+
+//! "Connect & use" orchestration: bring up a Bluetooth headset, wait for its
+//! audio endpoint to arrive, then make that endpoint the default output.
+//! Pure state machine: the event loop feeds it connect results, endpoint lists
+//! and clock ticks, and executes the returned `Step`. No threads or timers live
+//! here, so every transition is deterministic for a given input sequence.
+
+use std::fmt;
+use std::time::Instant;
+use crate::audio_device::AudioDevice;
+use crate::config::{BT_CONNECT_TIMEOUT, ENDPOINT_ARRIVAL_TIMEOUT};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    /// Bluetooth link being established (service toggle in flight).
+    Connecting { deadline: Instant },
+    /// Link is up; waiting for Core Audio to publish the endpoint.
+    AwaitingEndpoint { deadline: Instant },
+}
+
+/// Who started the orchestration; decides how its outcome is reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Origin {
+    /// Tray menu click: failures are shown right away.
+    User,
+    /// Reconnect-after-resume attempt: failures go to the retry policy.
+    Resume,
+}
+
+/// What the event loop should do after feeding an input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    /// Nothing to do yet; wake up again at `deadline()`.
+    Pending,
+    /// Endpoint arrived: make this endpoint ID the default output.
+    SetDefault(String),
+    /// Orchestration aborted; report to the user.
+    Failed(ConnectFailure),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectFailure {
+    ConnectError(String),
+    ConnectTimedOut,
+    EndpointTimedOut,
+}
+
+impl fmt::Display for ConnectFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ConnectError(e) => write!(f, "Bluetooth connection failed: {e}"),
+            Self::ConnectTimedOut => write!(f, "Bluetooth connection timed out after {} s", BT_CONNECT_TIMEOUT.as_secs()),
+            Self::EndpointTimedOut => write!(f, "Connected, but no audio endpoint appeared within {} s", ENDPOINT_ARRIVAL_TIMEOUT.as_secs()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ConnectAndSwitch {
+    address: u64,
+    device_name: String,
+    origin: Origin,
+    phase: Phase,
+}
+
+impl ConnectAndSwitch {
+    /// Begin orchestration. Caller starts the actual Bluetooth connection
+    /// unless `already_connected`, in which case we only wait for the endpoint.
+    pub fn start(address: u64, device_name: &str, origin: Origin, already_connected: bool, now: Instant) -> Self {
+        let phase = if already_connected {
+            Phase::AwaitingEndpoint { deadline: now + ENDPOINT_ARRIVAL_TIMEOUT }
+        } else {
+            Phase::Connecting { deadline: now + BT_CONNECT_TIMEOUT }
+        };
+        Self { address, device_name: device_name.to_string(), origin, phase }
+    }
+
+    pub fn address(&self) -> u64 { self.address }
+    pub fn device_name(&self) -> &str { &self.device_name }
+    pub fn origin(&self) -> Origin { self.origin }
+
+    pub fn deadline(&self) -> Instant {
+        match self.phase { Phase::Connecting { deadline } | Phase::AwaitingEndpoint { deadline } => deadline }
+    }
+
+    /// Bluetooth connection attempt finished (successfully or not).
+    pub fn on_connect_result(&mut self, result: Result<(), String>, endpoints: &[AudioDevice], now: Instant) -> Step {
+        if let Err(e) = result { return Step::Failed(ConnectFailure::ConnectError(e)); }
+        if let Phase::Connecting { .. } = self.phase {
+            self.phase = Phase::AwaitingEndpoint { deadline: now + ENDPOINT_ARRIVAL_TIMEOUT };
+        }
+        self.on_endpoints_changed(endpoints)
+    }
+
+    /// Endpoint list changed (IMMNotificationClient). The endpoint may show up
+    /// before the connect call returns, so any phase accepts a match.
+    pub fn on_endpoints_changed(&mut self, endpoints: &[AudioDevice]) -> Step {
+        match matching_endpoint(&self.device_name, endpoints) {
+            Some(device) => Step::SetDefault(device.id.clone()),
+            None => Step::Pending,
+        }
+    }
+
+    /// Clock tick; fails the phase whose deadline has passed.
+    pub fn on_tick(&mut self, now: Instant) -> Step {
+        match self.phase {
+            Phase::Connecting { deadline } if now >= deadline => Step::Failed(ConnectFailure::ConnectTimedOut),
+            Phase::AwaitingEndpoint { deadline } if now >= deadline => Step::Failed(ConnectFailure::EndpointTimedOut),
+            _ => Step::Pending,
+        }
+    }
+}
+
+/// Find the render endpoint belonging to a Bluetooth device by friendly name
+/// ("Headphones (WH-1000XM4)"). Stereo endpoints win over hands-free ones.
+pub fn matching_endpoint<'a>(device_name: &str, endpoints: &'a [AudioDevice]) -> Option<&'a AudioDevice> {
+    let needle = device_name.to_lowercase();
+    endpoints.iter()
+        .filter(|d| d.is_active() && d.name.to_lowercase().contains(&needle))
+        .min_by_key(|d| d.name.to_lowercase().contains("hands-free"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use crate::audio_device::EndpointState;
+
+    const ADDRESS: u64 = 0x0011_2233_4455;
+
+    fn endpoints() -> Vec<AudioDevice> {
+        vec![
+            AudioDevice::sample("speakers", "Speakers (Realtek Audio)"),
+            AudioDevice::sample("hands-free", "Headset (WH-1000XM4 Hands-Free AG Audio)"),
+            AudioDevice::sample("stereo", "Headphones (WH-1000XM4)"),
+        ]
+    }
+
+    #[test]
+    fn connecting_moves_to_awaiting_endpoint() {
+        let start = Instant::now();
+        let mut flow = ConnectAndSwitch::start(ADDRESS, "WH-1000XM4", Origin::User, false, start);
+        assert_eq!(flow.deadline(), start + BT_CONNECT_TIMEOUT);
+        assert_eq!(flow.on_tick(start + BT_CONNECT_TIMEOUT / 2), Step::Pending);
+
+        let connected = start + Duration::from_secs(3);
+        assert_eq!(flow.on_connect_result(Ok(()), &[], connected), Step::Pending);
+        assert_eq!(flow.deadline(), connected + ENDPOINT_ARRIVAL_TIMEOUT);
+        assert_eq!(flow.on_tick(connected + ENDPOINT_ARRIVAL_TIMEOUT - Duration::from_millis(1)), Step::Pending);
+        assert_eq!(flow.on_endpoints_changed(&endpoints()), Step::SetDefault("stereo".to_string()));
+    }
+
+    #[test]
+    fn endpoint_arriving_before_connect_result_is_taken() {
+        let start = Instant::now();
+        let mut flow = ConnectAndSwitch::start(ADDRESS, "WH-1000XM4", Origin::User, false, start);
+        assert_eq!(flow.on_endpoints_changed(&endpoints()), Step::SetDefault("stereo".to_string()));
+    }
+
+    #[test]
+    fn connect_error_fails() {
+        let start = Instant::now();
+        let mut flow = ConnectAndSwitch::start(ADDRESS, "WH-1000XM4", Origin::User, false, start);
+        let step = flow.on_connect_result(Err("device unreachable".to_string()), &endpoints(), start);
+        assert_eq!(step, Step::Failed(ConnectFailure::ConnectError("device unreachable".to_string())));
+    }
+
+    #[test]
+    fn connect_timeout_fails() {
+        let start = Instant::now();
+        let mut flow = ConnectAndSwitch::start(ADDRESS, "WH-1000XM4", Origin::User, false, start);
+        assert_eq!(flow.on_tick(start + BT_CONNECT_TIMEOUT - Duration::from_millis(1)), Step::Pending);
+        assert_eq!(flow.on_tick(start + BT_CONNECT_TIMEOUT), Step::Failed(ConnectFailure::ConnectTimedOut));
+    }
+
+    #[test]
+    fn endpoint_timeout_fails() {
+        let start = Instant::now();
+        let mut flow = ConnectAndSwitch::start(ADDRESS, "WH-1000XM4", Origin::User, false, start);
+        let connected = start + Duration::from_secs(1);
+        assert_eq!(flow.on_connect_result(Ok(()), &[], connected), Step::Pending);
+        assert_eq!(flow.on_tick(connected + ENDPOINT_ARRIVAL_TIMEOUT), Step::Failed(ConnectFailure::EndpointTimedOut));
+    }
+
+    #[test]
+    fn already_connected_start_only_waits_for_endpoint() {
+        let start = Instant::now();
+        let mut flow = ConnectAndSwitch::start(ADDRESS, "WH-1000XM4", Origin::User, true, start);
+        assert_eq!(flow.address(), ADDRESS);
+        assert_eq!(flow.device_name(), "WH-1000XM4");
+        assert_eq!(flow.deadline(), start + ENDPOINT_ARRIVAL_TIMEOUT);
+        assert_eq!(flow.on_tick(start + ENDPOINT_ARRIVAL_TIMEOUT), Step::Failed(ConnectFailure::EndpointTimedOut));
+
+        let mut flow = ConnectAndSwitch::start(ADDRESS, "WH-1000XM4", Origin::User, true, start);
+        assert_eq!(flow.on_endpoints_changed(&endpoints()), Step::SetDefault("stereo".to_string()));
+    }
+
+    #[test]
+    fn matching_endpoint_prefers_stereo_over_hands_free() {
+        let mut devices = endpoints();
+        assert_eq!(matching_endpoint("wh-1000xm4", &devices).map(|d| d.id.as_str()), Some("stereo"));
+        // Hands-free is used when it is the only active endpoint of the device
+        devices[2].state = EndpointState::Unplugged;
+        assert_eq!(matching_endpoint("WH-1000XM4", &devices).map(|d| d.id.as_str()), Some("hands-free"));
+        assert!(matching_endpoint("Jabra Elite", &devices).is_none());
+    }
+}
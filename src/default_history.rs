@@ -0,0 +1,143 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2025 Triffit
+
+//! Default outputs chosen by the user (menu, hotkey, "Connect and use"), most
+//! recent first. When the current default goes away, Windows falls back to
+//! whatever it ranks next (often an HDMI monitor without speakers); with
+//! `restore_previous_default` the most recent earlier choice that is still
+//! active is made the default instead. Works against any `AudioBackend`.
+//! A choice also overrides `output_priority` auto-selection until the next
+//! device change.
+
+use crate::audio_device::{AudioBackend, AudioDevice, AudioFlow, AudioRole};
+use crate::config::AppResult;
+
+/// Earlier choices beyond this are unlikely to be what the user wants back.
+const MAX_HISTORY: usize = 16;
+
+#[derive(Debug, Default)]
+pub struct DefaultHistory {
+    ids: Vec<String>,
+    user_override: bool, // a choice was made since the last device change
+}
+
+impl DefaultHistory {
+    /// History seeded with the default found at startup (not a user choice, so no override).
+    pub fn new(current: Option<&str>) -> Self {
+        Self { ids: current.into_iter().map(str::to_string).collect(), user_override: false }
+    }
+
+    /// Note a default output set by the user.
+    pub fn record(&mut self, id: &str) {
+        self.ids.retain(|existing| existing != id);
+        self.ids.insert(0, id.to_string());
+        self.ids.truncate(MAX_HISTORY);
+        self.user_override = true;
+    }
+
+    /// An endpoint was added, removed or changed state: the override ends.
+    pub fn on_device_change(&mut self) { self.user_override = false; }
+
+    pub fn user_override(&self) -> bool { self.user_override }
+
+    /// The user's current choice.
+    pub fn latest(&self) -> Option<&str> { self.ids.first().map(String::as_str) }
+
+    /// Most recent previous default other than `lost` that is among the active `devices`.
+    pub fn restore_target<'a>(&self, lost: &str, devices: &'a [AudioDevice]) -> Option<&'a AudioDevice> {
+        self.ids.iter()
+            .filter(|id| *id != lost)
+            .find_map(|id| devices.iter().find(|d| d.id == *id && d.is_active()))
+    }
+
+    /// The default output `lost` was removed or disconnected: make the restore target the
+    /// Console + Multimedia default (a separately chosen communications device stays). Returns
+    /// it, or None if there is none or it already is the default.
+    pub fn restore_after(&self, lost: &str, audio: &dyn AudioBackend) -> AppResult<Option<AudioDevice>> {
+        let devices = audio.enumerate(AudioFlow::Output, false)?;
+        let Some(target) = self.restore_target(lost, &devices).filter(|d| !d.is_default) else { return Ok(None) };
+        audio.set_default_roles(&target.id, &AudioRole::MEDIA)?;
+        Ok(Some(target.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio_device::EndpointState;
+    use crate::audio_fake::FakeAudioBackend;
+
+    const SPEAKERS: &str = "{fake}.speakers";
+    const HEADPHONES: &str = "{fake}.headphones";
+    const HEADSET: &str = "{fake}.headset";
+
+    fn history(choices: &[&str]) -> DefaultHistory {
+        let mut history = DefaultHistory::new(None);
+        for id in choices { history.record(id); }
+        history
+    }
+
+    #[test]
+    fn record_moves_a_choice_to_the_front() {
+        let mut history = DefaultHistory::new(Some("a"));
+        assert!(!history.user_override());
+        history.record("b");
+        history.record("a");
+        assert_eq!(history.ids, ["a", "b"]);
+        assert_eq!(history.latest(), Some("a"));
+        assert!(history.user_override());
+        history.on_device_change();
+        assert!(!history.user_override());
+    }
+
+    #[test]
+    fn record_keeps_the_most_recent_choices() {
+        let ids: Vec<String> = (0..MAX_HISTORY + 4).map(|i| format!("device-{i}")).collect();
+        let mut history = DefaultHistory::default();
+        for id in &ids { history.record(id); }
+        assert_eq!(history.ids.len(), MAX_HISTORY);
+        assert_eq!(history.latest(), ids.last().map(String::as_str));
+        assert!(!history.ids.contains(&ids[3]));
+        assert!(history.ids.contains(&ids[4]));
+    }
+
+    #[test]
+    fn restore_target_skips_the_lost_and_inactive_devices() {
+        let devices = [
+            AudioDevice::sample("a", "A"),
+            AudioDevice { state: EndpointState::Unplugged, ..AudioDevice::sample("b", "B") },
+            AudioDevice::sample("c", "C"),
+        ];
+        let history = history(&["a", "c", "b", "gone"]);
+        assert_eq!(history.restore_target("gone", &devices).map(|d| d.id.as_str()), Some("c"));
+        assert_eq!(history.restore_target("c", &devices).map(|d| d.id.as_str()), Some("a"));
+        assert!(DefaultHistory::new(Some("b")).restore_target("a", &devices).is_none());
+    }
+
+    #[test]
+    fn restore_after_switches_back_to_the_previous_choice() {
+        let fake = FakeAudioBackend::with_sample_devices();
+        let history = history(&[HEADSET, HEADPHONES]);
+        fake.set_default_roles(HEADPHONES, &AudioRole::MEDIA).unwrap();
+        // Calls stay on the speakers, chosen separately
+        fake.change_default_externally(SPEAKERS, AudioRole::Communications);
+        // Windows falls back to the speakers
+        fake.remove_device(HEADPHONES);
+        let restored = history.restore_after(HEADPHONES, &fake).unwrap();
+        assert_eq!(restored.map(|d| d.id), Some(HEADSET.to_string()));
+        for role in AudioRole::MEDIA {
+            assert_eq!(fake.default_device(AudioFlow::Output, role).unwrap().as_deref(), Some(HEADSET));
+        }
+        assert_eq!(fake.default_device(AudioFlow::Output, AudioRole::Communications).unwrap().as_deref(), Some(SPEAKERS));
+    }
+
+    #[test]
+    fn restore_after_leaves_a_target_that_already_is_default() {
+        let fake = FakeAudioBackend::with_sample_devices();
+        let history = history(&[SPEAKERS, HEADPHONES]);
+        fake.set_default_all_roles(HEADPHONES).unwrap();
+        fake.remove_device(HEADPHONES);
+        assert!(history.restore_after(HEADPHONES, &fake).unwrap().is_none());
+        assert_eq!(fake.default_device(AudioFlow::Output, AudioRole::Console).unwrap().as_deref(), Some(SPEAKERS));
+    }
+}
@@ -0,0 +1,86 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2025 Triffit
+
+//! "Lock current device": while on, the default output is kept on the user's
+//! latest choice (`DefaultHistory`). Changes made by Windows or other
+//! applications (Teams, vendor audio consoles) are reverted after the next audio
+//! refresh; the app's own switches are recorded as the latest choice before they
+//! happen, so they are never reverted. Loop protection: once the reverts within
+//! `LOCK_REVERT_WINDOW` exceed `MAX_LOCK_REVERTS`, the lock releases itself
+//! rather than fighting another program forever.
+
+use std::collections::VecDeque;
+use std::time::Instant;
+use crate::config::{LOCK_REVERT_WINDOW, MAX_LOCK_REVERTS};
+
+#[derive(Debug, Default)]
+pub struct DefaultLock {
+    enabled: bool,
+    reverts: VecDeque<Instant>, // within the window, oldest first
+}
+
+impl DefaultLock {
+    pub fn is_enabled(&self) -> bool { self.enabled }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        self.reverts.clear();
+    }
+
+    /// Account for a revert at `now`. False (and the lock released) when it would exceed the limit.
+    pub fn allow_revert(&mut self, now: Instant) -> bool {
+        while self.reverts.front().is_some_and(|&t| now.duration_since(t) >= LOCK_REVERT_WINDOW) {
+            self.reverts.pop_front();
+        }
+        if self.reverts.len() >= MAX_LOCK_REVERTS {
+            self.set_enabled(false);
+            return false;
+        }
+        self.reverts.push_back(now);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn enabled() -> DefaultLock {
+        let mut lock = DefaultLock::default();
+        lock.set_enabled(true);
+        lock
+    }
+
+    #[test]
+    fn limit_releases_the_lock() {
+        let start = Instant::now();
+        let mut lock = enabled();
+        for i in 0..MAX_LOCK_REVERTS as u32 {
+            assert!(lock.allow_revert(start + Duration::from_secs(i.into())));
+        }
+        assert!(!lock.allow_revert(start + Duration::from_secs(MAX_LOCK_REVERTS as u64)));
+        assert!(!lock.is_enabled());
+    }
+
+    #[test]
+    fn reverts_outside_the_window_do_not_count() {
+        let start = Instant::now();
+        let mut lock = enabled();
+        for _ in 0..MAX_LOCK_REVERTS { assert!(lock.allow_revert(start)); }
+        // Exactly one window later the first burst has expired
+        let later = start + LOCK_REVERT_WINDOW;
+        for _ in 0..MAX_LOCK_REVERTS { assert!(lock.allow_revert(later)); }
+        assert!(!lock.allow_revert(later + LOCK_REVERT_WINDOW - Duration::from_millis(1)));
+    }
+
+    #[test]
+    fn re_enabling_starts_a_fresh_count() {
+        let now = Instant::now();
+        let mut lock = enabled();
+        for _ in 0..MAX_LOCK_REVERTS { assert!(lock.allow_revert(now)); }
+        lock.set_enabled(true);
+        assert!(lock.allow_revert(now));
+        assert!(lock.is_enabled());
+    }
+}
@@ -0,0 +1,186 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2025 Triffit
+
+//! Audio menu ordering: pinned devices first (in pin order), then the rest by
+//! the configured sort mode. `IMMDeviceCollection` order changes whenever a
+//! device reconnects, so the menu order is computed here instead.
+//! The most-recently-used history is kept in `recent_audio_devices.txt` next to
+//! the settings file (one endpoint ID per line, most recent first).
+
+use std::cmp::Ordering;
+use std::path::PathBuf;
+use crate::audio_device::AudioDevice;
+use crate::log_dbg;
+use crate::settings::app_data_dir;
+
+/// Remembered devices per flow are few; keep the file short.
+const MAX_RECENT: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortMode {
+    /// Order reported by Windows.
+    #[default]
+    System,
+    Alphabetical,
+    /// Most recently used as default first.
+    Recent,
+    /// Grouped by `ConnectionType`, alphabetical within a group.
+    Connection,
+    /// Grouped by `FormFactor` (speakers, headphones, …), alphabetical within a group.
+    Type,
+}
+
+impl SortMode {
+    pub fn parse(text: &str) -> Option<Self> {
+        match text.to_ascii_lowercase().as_str() {
+            "system" => Some(SortMode::System),
+            "alphabetical" | "name" => Some(SortMode::Alphabetical),
+            "recent" | "mru" => Some(SortMode::Recent),
+            "connection" => Some(SortMode::Connection),
+            "type" | "form_factor" => Some(SortMode::Type),
+            _ => None,
+        }
+    }
+}
+
+/// Sort in place: pinned IDs first in `pinned` order, then the others by `mode`.
+/// Stable, so ties keep the system order. IDs compare case-insensitively.
+pub fn order_devices(devices: &mut [AudioDevice], mode: SortMode, pinned: &[String], recent: &[String]) {
+    let rank = |ids: &[String], device: &AudioDevice| ids.iter().position(|id| id.eq_ignore_ascii_case(&device.id)).unwrap_or(usize::MAX);
+    let by_name = |a: &AudioDevice, b: &AudioDevice| a.display_name().to_lowercase().cmp(&b.display_name().to_lowercase());
+    devices.sort_by(|a, b| {
+        rank(pinned, a).cmp(&rank(pinned, b)).then_with(|| match mode {
+            SortMode::System => Ordering::Equal,
+            SortMode::Alphabetical => by_name(a, b),
+            SortMode::Recent => rank(recent, a).cmp(&rank(recent, b)),
+            SortMode::Connection => a.connection.cmp(&b.connection).then_with(|| by_name(a, b)),
+            SortMode::Type => a.form_factor.cmp(&b.form_factor).then_with(|| by_name(a, b)),
+        })
+    });
+}
+
+/// Most-recently-used default devices (both flows), most recent first.
+#[derive(Debug, Default)]
+pub struct RecentDevices {
+    ids: Vec<String>,
+    /// Backing file (None = kept in memory only).
+    path: Option<PathBuf>,
+}
+
+impl RecentDevices {
+    pub fn load() -> Self { Self::load_from(recent_path()) }
+
+    fn load_from(path: Option<PathBuf>) -> Self {
+        let ids = path.as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .map(|text| text.lines().map(str::trim).filter(|l| !l.is_empty()).map(str::to_string).collect())
+            .unwrap_or_default();
+        Self { ids, path }
+    }
+
+    pub fn ids(&self) -> &[String] { &self.ids }
+
+    /// Move `current` (the defaults of each flow) to the front; the file is only
+    /// rewritten when the order changed.
+    pub fn touch(&mut self, current: &[String]) {
+        let unchanged = self.ids.len() >= current.len()
+            && self.ids.iter().zip(current).all(|(a, b)| a.eq_ignore_ascii_case(b));
+        if unchanged { return; }
+        self.ids.retain(|existing| !current.iter().any(|id| id.eq_ignore_ascii_case(existing)));
+        self.ids.splice(0..0, current.iter().cloned());
+        self.ids.truncate(MAX_RECENT);
+        self.save();
+    }
+
+    fn save(&self) {
+        let Some(path) = &self.path else { return };
+        let result = path.parent().map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|()| std::fs::write(path, self.ids.join("\n")));
+        if let Err(_e) = result {
+            log_dbg!("device-order: saving {} failed: {_e}", path.display());
+        }
+    }
+}
+
+fn recent_path() -> Option<PathBuf> {
+    app_data_dir().map(|dir| dir.join("recent_audio_devices.txt"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio_device::{ConnectionType, FormFactor};
+
+    fn ids(devices: &[AudioDevice]) -> Vec<&str> { devices.iter().map(|d| d.id.as_str()).collect() }
+
+    fn strings(ids: &[&str]) -> Vec<String> { ids.iter().map(|id| id.to_string()).collect() }
+
+    /// System order: c, A (pinned), b, d; names deliberately not in ID order.
+    fn devices() -> Vec<AudioDevice> {
+        vec![
+            AudioDevice { connection: ConnectionType::Usb, form_factor: FormFactor::Headphones, ..AudioDevice::sample("c", "Zeta") },
+            AudioDevice { connection: ConnectionType::Bluetooth, form_factor: FormFactor::Headset, ..AudioDevice::sample("A", "Beta") },
+            AudioDevice { connection: ConnectionType::Builtin, form_factor: FormFactor::Speakers, ..AudioDevice::sample("b", "alpha") },
+            AudioDevice { connection: ConnectionType::Usb, form_factor: FormFactor::Speakers, ..AudioDevice::sample("d", "Gamma") },
+        ]
+    }
+
+    #[test]
+    fn pinned_devices_first_in_pin_order() {
+        let mut list = devices();
+        order_devices(&mut list, SortMode::System, &strings(&["D", "a", "missing"]), &[]);
+        assert_eq!(ids(&list), ["d", "A", "c", "b"]);
+    }
+
+    #[test]
+    fn sort_modes() {
+        let recent = strings(&["b", "c"]);
+        let cases = [
+            (SortMode::System, ["c", "A", "b", "d"]),
+            (SortMode::Alphabetical, ["b", "A", "d", "c"]),
+            (SortMode::Recent, ["b", "c", "A", "d"]),
+            (SortMode::Connection, ["b", "d", "c", "A"]),
+            (SortMode::Type, ["b", "d", "c", "A"]),
+        ];
+        for (mode, expected) in cases {
+            let mut list = devices();
+            order_devices(&mut list, mode, &[], &recent);
+            assert_eq!(ids(&list), expected, "{mode:?}");
+        }
+    }
+
+    #[test]
+    fn ties_keep_system_order() {
+        let mut list = vec![AudioDevice::sample("x", "Same"), AudioDevice::sample("y", "same"), AudioDevice::sample("z", "Same")];
+        order_devices(&mut list, SortMode::Alphabetical, &[], &[]);
+        assert_eq!(ids(&list), ["x", "y", "z"]);
+        order_devices(&mut list, SortMode::Recent, &[], &strings(&["unrelated"]));
+        assert_eq!(ids(&list), ["x", "y", "z"]);
+    }
+
+    #[test]
+    fn sort_mode_parse() {
+        assert_eq!(SortMode::parse("MRU"), Some(SortMode::Recent));
+        assert_eq!(SortMode::parse("form_factor"), Some(SortMode::Type));
+        assert_eq!(SortMode::parse("random"), None);
+    }
+
+    #[test]
+    fn touch_rewrites_only_when_the_order_changes() {
+        let path = std::env::temp_dir().join(format!("recent_audio_devices_{}.txt", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut recent = RecentDevices::load_from(Some(path.clone()));
+        recent.touch(&strings(&["out", "in"]));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "out\nin");
+
+        // Same defaults (any case): the file is left alone
+        std::fs::write(&path, "marker").unwrap();
+        recent.touch(&strings(&["OUT", "in"]));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "marker");
+
+        recent.touch(&strings(&["other", "in"]));
+        assert_eq!(recent.ids(), strings(&["other", "in", "out"]));
+        assert_eq!(RecentDevices::load_from(Some(path.clone())).ids(), recent.ids());
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,73 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2025 Triffit
+
+//! Endpoints of one physical device (same `PKEY_Device_ContainerId`), e.g. a
+//! Bluetooth headset's "Headphones" (A2DP stereo) and "Headset" (HFP
+//! hands-free) outputs, are listed as one menu entry. Clicking it assigns the
+//! stereo endpoint to Console + Multimedia and the hands-free one to
+//! Communications; the individual endpoints stay reachable in the "Endpoints"
+//! submenu. Only active endpoints are grouped.
+
+use crate::audio_device::{AudioDevice, AudioRole};
+
+/// Container ID reported by everything built into the computer (codecs, HDMI
+/// outputs), so it never identifies a separate physical device.
+const LOCAL_MACHINE_CONTAINER: &str = "{00000000-0000-0000-ffff-ffffffffffff}";
+
+/// One entry of an audio device list; indices refer to the device list it was built from.
+#[derive(Debug, Clone)]
+pub struct EndpointGroup {
+    /// Container ID, or the endpoint ID of an endpoint that is not grouped.
+    pub key: String,
+    /// Members in list order.
+    pub members: Vec<usize>,
+    /// Endpoint for Console + Multimedia (the first non-hands-free member).
+    pub media: usize,
+    /// Endpoint for Communications (the first hands-free member, else `media`).
+    pub communications: usize,
+}
+
+/// Group `devices` (in menu order) by container; a group takes the position of its first member.
+pub fn group_endpoints(devices: &[AudioDevice]) -> Vec<EndpointGroup> {
+    let mut groups: Vec<EndpointGroup> = Vec::new();
+    for (idx, device) in devices.iter().enumerate() {
+        let container = device.container_id.as_deref()
+            .filter(|c| device.is_active() && !c.eq_ignore_ascii_case(LOCAL_MACHINE_CONTAINER));
+        match container.and_then(|c| groups.iter_mut().find(|g| g.key.eq_ignore_ascii_case(c))) {
+            Some(group) => group.members.push(idx),
+            None => {
+                let key = container.unwrap_or(&device.id).to_string();
+                groups.push(EndpointGroup { key, members: vec![idx], media: idx, communications: idx });
+            }
+        }
+    }
+    for group in &mut groups {
+        let hands_free = |&idx: &usize| devices[idx].is_hands_free();
+        group.media = group.members.iter().copied().find(|idx| !hands_free(idx)).unwrap_or(group.members[0]);
+        group.communications = group.members.iter().copied().find(hands_free).unwrap_or(group.media);
+    }
+    groups
+}
+
+impl EndpointGroup {
+    pub fn is_group(&self) -> bool { self.members.len() > 1 }
+
+    /// The media endpoint, marked default / communications default if any member is.
+    pub fn entry(&self, devices: &[AudioDevice]) -> AudioDevice {
+        let mut entry = devices[self.media].clone();
+        entry.is_default = self.members.iter().any(|&idx| devices[idx].is_default);
+        entry.is_communications_default = self.members.iter().any(|&idx| devices[idx].is_communications_default);
+        entry
+    }
+
+    /// Roles to assign when the entry is clicked (all roles when there is no separate hands-free endpoint).
+    pub fn assignments<'a>(&self, devices: &'a [AudioDevice]) -> Vec<(&'a AudioDevice, &'static [AudioRole])> {
+        let (all, media, communications): (&'static [AudioRole], &'static [AudioRole], &'static [AudioRole]) =
+            (&AudioRole::ALL, &AudioRole::MEDIA, &[AudioRole::Communications]);
+        if self.media == self.communications {
+            vec![(&devices[self.media], all)]
+        } else {
+            vec![(&devices[self.media], media), (&devices[self.communications], communications)]
+        }
+    }
+}
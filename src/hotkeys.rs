@@ -0,0 +1,107 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2025 Triffit
+
+//! Optional global hotkeys (`RegisterHotKey`) configured in settings.
+//! Runs its own message thread like the Win+K hook; `WM_HOTKEY` is posted to
+//! that thread's queue and forwarded to the callback as a `HotkeyAction`.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use windows_sys::Win32::System::Threading::GetCurrentThreadId;
+use windows_sys::Win32::UI::Input::KeyboardAndMouse::{
+    RegisterHotKey, UnregisterHotKey, HOT_KEY_MODIFIERS, MOD_ALT, MOD_CONTROL, MOD_NOREPEAT, MOD_SHIFT, MOD_WIN,
+};
+use windows_sys::Win32::UI::WindowsAndMessaging::{GetMessageW, PostThreadMessageW, MSG, WM_HOTKEY, WM_QUIT};
+use crate::config::AppResult;
+use crate::log_dbg;
+
+static HOTKEY_THREAD_ID: AtomicU32 = AtomicU32::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HotkeyAction {
+    ToggleRadio,
+    CycleOutput,
+}
+
+/// Modifier + virtual key combination, parsed from e.g. "Ctrl+Alt+B".
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hotkey {
+    modifiers: HOT_KEY_MODIFIERS,
+    vk: u32,
+}
+
+impl Hotkey {
+    /// Accepts `+`-separated modifiers (Ctrl, Alt, Shift, Win) and one key:
+    /// A-Z, 0-9 or F1-F24. Case-insensitive; requires at least one modifier.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut modifiers = 0;
+        let mut vk = None;
+        for part in text.split('+').map(str::trim) {
+            match part.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => modifiers |= MOD_CONTROL,
+                "alt" => modifiers |= MOD_ALT,
+                "shift" => modifiers |= MOD_SHIFT,
+                "win" => modifiers |= MOD_WIN,
+                key if vk.is_none() => vk = Some(key_code(key)?),
+                _ => return None,
+            }
+        }
+        if modifiers == 0 { return None; }
+        Some(Self { modifiers, vk: vk? })
+    }
+}
+
+fn key_code(key: &str) -> Option<u32> {
+    let bytes = key.as_bytes();
+    match bytes {
+        [c] if c.is_ascii_alphanumeric() => Some(u32::from(c.to_ascii_uppercase())),
+        [b'f', rest @ ..] if !rest.is_empty() => {
+            let n: u32 = key[1..].parse().ok()?;
+            (1..=24).contains(&n).then_some(0x70 + n - 1) // VK_F1 = 0x70
+        }
+        _ => None,
+    }
+}
+
+pub struct HotkeyGuard(Option<JoinHandle<()>>);
+
+impl Drop for HotkeyGuard {
+    fn drop(&mut self) {
+        let tid = HOTKEY_THREAD_ID.load(Ordering::SeqCst);
+        if tid != 0 { unsafe { PostThreadMessageW(tid, WM_QUIT, 0, 0); } }
+        if let Some(handle) = self.0.take() {
+            // Best-effort join (same pattern as the keyboard hook guard)
+            let start = Instant::now();
+            while start.elapsed() < Duration::from_millis(200) {
+                if handle.is_finished() { let _ = handle.join(); break; }
+                std::thread::sleep(Duration::from_millis(10));
+            }
+        }
+    }
+}
+
+/// Register the given bindings; keys already taken by another app are skipped (logged).
+pub fn register_hotkeys<F: Fn(HotkeyAction) + Send + 'static>(bindings: Vec<(HotkeyAction, Hotkey)>, callback: F) -> AppResult<HotkeyGuard> {
+    let (tx, rx) = std::sync::mpsc::channel();
+    let handle = std::thread::spawn(move || {
+        HOTKEY_THREAD_ID.store(unsafe { GetCurrentThreadId() }, Ordering::SeqCst);
+        for (id, (_action, hotkey)) in bindings.iter().enumerate() {
+            if unsafe { RegisterHotKey(std::ptr::null_mut(), id as i32, hotkey.modifiers | MOD_NOREPEAT, hotkey.vk) } == 0 {
+                log_dbg!("hotkeys: {_action:?} already in use by another application");
+            }
+        }
+        let _ = tx.send(());
+        let mut msg: MSG = unsafe { std::mem::zeroed() };
+        while unsafe { GetMessageW(&mut msg, std::ptr::null_mut(), 0, 0) } > 0 {
+            if msg.message == WM_HOTKEY {
+                if let Some((action, _)) = bindings.get(msg.wParam) { callback(*action); }
+            }
+        }
+        for id in 0..bindings.len() { unsafe { UnregisterHotKey(std::ptr::null_mut(), id as i32); } }
+    });
+    match rx.recv_timeout(Duration::from_secs(2)) {
+        Ok(()) => Ok(HotkeyGuard(Some(handle))),
+        Err(_) => Err("Timed out waiting for hotkey registration".into()),
+    }
+}
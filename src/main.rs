@@ -6,6 +6,8 @@
 #[macro_use]
 mod log; // exports log_dbg! macro
 mod audio_device;
+#[cfg(any(test, feature = "fake-backends"))]
+mod audio_fake;
 mod bluetooth;
 mod tray;
 mod keyboard_hook;
@@ -13,12 +15,44 @@ mod app_state;
 mod single_instance;
 mod utf16_strings;
 mod config;
+mod connect_flow;
+mod battery;
+mod settings;
+mod notify;
+mod bt_watcher;
+mod radio;
+mod hotkeys;
+mod resume;
+mod device_order;
+mod coalesce;
+mod menu_sync;
+mod default_history;
+mod auto_select;
+mod default_lock;
+mod endpoint_group;
+mod wave_format;
+mod power;
 
 use app_state::AppState;
-use audio_device::{set_default_audio_device, register_device_change_callback};
+use connect_flow::{ConnectAndSwitch, Origin, Step};
+use battery::LowBatteryWatch;
+use bluetooth::{device_for_endpoint, BluetoothDevice};
+use tray::{Discovery, VolumeAction};
+use settings::Settings;
+use config::{AUDIO_REFRESH_MAX_DELAY, BATTERY_REFRESH_INTERVAL};
+use coalesce::Coalescer;
+use default_history::DefaultHistory;
+use auto_select::OutputPriority;
+use default_lock::DefaultLock;
+use hotkeys::{register_hotkeys, HotkeyAction};
+use radio::{RadioControl, RadioState};
+use resume::{ResumeReconnect, RetryDecision};
+use std::sync::Arc;
+use audio_device::{next_device, AudioBackend, AudioDeviceEvent, AudioFlow, AudioRole, Subscription};
 use single_instance::{ensure_single_instance_wide, InstanceCheck};
-use winit::event_loop::{ControlFlow, EventLoopBuilder};
-use winit::event::{Event, WindowEvent};
+use winit::event_loop::{ControlFlow, EventLoopBuilder, EventLoopProxy};
+use winit::event::{Event, StartCause, WindowEvent};
+use std::time::Instant;
 use tray_icon::{TrayIconEvent, menu::{MenuEvent}};
 
 // Version injected by build.rs (fallback to placeholder if missing)
@@ -26,15 +60,32 @@ const VERSION: &str = match option_env!("APP_VERSION") { Some(v) => v, None => "
 use crate::utf16_strings::UTF16_MUTEX_NAME;
 
 #[derive(Debug)]
-enum UserEvent { TrayEvent(TrayIconEvent), MenuEvent(MenuEvent), WinKHook, RefreshAudioDevices }
+enum UserEvent {
+    TrayEvent(TrayIconEvent), MenuEvent(MenuEvent), WinKHook, AudioDeviceChanged(AudioDeviceEvent), RefreshAudioDevices, RefreshBluetoothDevices,
+    BluetoothConnectFinished(u64, Result<(), String>), // (device address, outcome) for "Connect and use"
+    BluetoothConnectionChanged { address: u64, connected: bool },
+    Hotkey(HotkeyAction),
+    DiscoveryFinished(Result<Vec<BluetoothDevice>, String>),
+    PairingFinished(u64, Result<(), String>), // (device address, outcome)
+    ResumedFromSleep,
+    AudioVolumeChanged,
+    RadioStateChanged(RadioState),
+}
 
 use crate::config::AppResult;
 
 fn main() -> AppResult {
+    // Audio endpoints (Core Audio, or an in-memory fake)
+    #[cfg(not(feature = "fake-backends"))]
+    let audio: Arc<dyn AudioBackend> = Arc::new(audio_device::CoreAudioBackend);
+    #[cfg(feature = "fake-backends")]
+    let audio: Arc<dyn AudioBackend> = Arc::new(audio_fake::FakeAudioBackend::with_sample_devices());
+
     // Early CLI flags (before windows_subsystem hides console in release)
     for arg in std::env::args().skip(1) {
         match arg.as_str() {
             "--version" | "-V" => { println!("ShowBTConnect {VERSION}"); return Ok(()); },
+            "--list-audio-devices" => { print_audio_devices(audio.as_ref())?; return Ok(()); },
             _ => {}
         }
     }
@@ -54,11 +105,27 @@ fn main() -> AppResult {
     let event_loop = EventLoopBuilder::<UserEvent>::with_user_event().build()?;
     let event_loop_proxy = event_loop.create_proxy();
     let mut state = AppState::new();
+    let settings = Settings::load();
 
-    // Tray & menu (reusable builder)
-    let mut tray_manager = tray::TrayManager::new()?;
-    let mut about_id = tray_manager.about_id().to_string();
-    let mut exit_id = tray_manager.exit_id().to_string();
+    // Bluetooth radio control (in-memory fake for UI work without hardware)
+    #[cfg(not(feature = "fake-backends"))]
+    let radio: Arc<dyn RadioControl> = Arc::new(radio::WinRtRadio);
+    #[cfg(feature = "fake-backends")]
+    let radio: Arc<dyn RadioControl> = Arc::new(radio::FakeRadio::default());
+
+    // Tray & menu (one icon for the lifetime of the app, updated in place)
+    let mut tray_manager = tray::TrayManager::new(radio, audio.clone(), settings.audio_rules.clone())?;
+    // Radio state: read once off the UI thread, then follow StateChanged
+    let radio_proxy = event_loop_proxy.clone();
+    let _radio_guard = match tray_manager.radio().subscribe(Arc::new(move |state| {
+        let _ = radio_proxy.send_event(UserEvent::RadioStateChanged(state));
+    })) {
+        Ok(guard) => Some(guard),
+        Err(_e) => { log_dbg!("radio: state notifications not available: {_e}"); None }
+    };
+    report_radio_state(tray_manager.radio(), event_loop_proxy.clone());
+    let about_id = tray_manager.about_id().to_string();
+    let exit_id = tray_manager.exit_id().to_string();
 
     // Keyboard hook -> user event
     let hook_proxy = event_loop_proxy.clone();
@@ -66,9 +133,43 @@ fn main() -> AppResult {
 
     // Audio device change notifications (event-driven, no polling!)
     let audio_proxy = event_loop_proxy.clone();
-    let _audio_notification_guard = register_device_change_callback(std::sync::Arc::new(move || {
-        let _ = audio_proxy.send_event(UserEvent::RefreshAudioDevices);
+    let _audio_notification_guard = audio.subscribe(Arc::new(move |event| {
+        let _ = audio_proxy.send_event(UserEvent::AudioDeviceChanged(event));
     }))?;
+    // Volume / mute changes of the active endpoints (re-subscribed when that set changes)
+    let mut _volume_guard = subscribe_volume(audio.as_ref(), event_loop_proxy.clone());
+    let mut volume_watched = tray_manager.active_endpoint_ids();
+
+    // Optional global hotkeys from settings
+    let hotkey_bindings: Vec<_> = [
+        (HotkeyAction::ToggleRadio, settings.radio_hotkey),
+        (HotkeyAction::CycleOutput, settings.cycle_output_hotkey),
+    ].into_iter().filter_map(|(action, key)| Some((action, key?))).collect();
+    let _hotkey_guard = if hotkey_bindings.is_empty() { None } else {
+        let hotkey_proxy = event_loop_proxy.clone();
+        match register_hotkeys(hotkey_bindings, move |action| { let _ = hotkey_proxy.send_event(UserEvent::Hotkey(action)); }) {
+            Ok(guard) => Some(guard),
+            Err(_e) => { log_dbg!("hotkeys: registration failed: {_e}"); None }
+        }
+    };
+
+    // Bluetooth connect/disconnect notifications (optional: machines without a radio just skip it)
+    let bt_proxy = event_loop_proxy.clone();
+    let _bt_watcher_guard = match bt_watcher::register_connection_watcher(std::sync::Arc::new(move |address, connected| {
+        let _ = bt_proxy.send_event(UserEvent::BluetoothConnectionChanged { address, connected });
+    })) {
+        Ok(guard) => Some(guard),
+        Err(_e) => { log_dbg!("bt-watcher: not available: {_e}"); None }
+    };
+
+    // Resume from sleep (only needed for the opt-in reconnect policy)
+    let _power_guard = if !settings.reconnect_after_resume { None } else {
+        let power_proxy = event_loop_proxy.clone();
+        match power::register_resume_callback(Arc::new(move || { let _ = power_proxy.send_event(UserEvent::ResumedFromSleep); })) {
+            Ok(guard) => Some(guard),
+            Err(_e) => { log_dbg!("power: resume notification not available: {_e}"); None }
+        }
+    };
 
     // Tray + menu handlers
     let proxy_clone = event_loop_proxy.clone();
@@ -78,10 +179,67 @@ fn main() -> AppResult {
 
     log_dbg!("core: started version {VERSION}");
 
+    // Active "Connect and use" orchestration (at most one at a time)
+    let mut connect_flow: Option<ConnectAndSwitch> = None;
+
+    // Reconnect-after-resume: last connected Bluetooth headset (address, name) and pending retries
+    let mut last_audio_device: Option<(u64, String)> = tray_manager.bluetooth_devices().iter()
+        .find(|d| d.connected && d.is_audio())
+        .map(|d| (d.address, d.name.clone()));
+    let mut resume_reconnect: Option<ResumeReconnect> = None;
+
+    // Outputs chosen by the user, to fall back to when the current default goes away
+    let current_default = tray_manager.audio_devices().iter().find(|d| d.is_default).map(|d| d.id.as_str());
+    let mut default_history = DefaultHistory::new(current_default);
+    auto_select_output(&settings.output_priority, &default_history, audio.as_ref(), &mut tray_manager);
+    let mut default_lock = DefaultLock::default();
+
+    // Battery levels have no change notification; re-read periodically while a device is connected.
+    let mut low_battery = LowBatteryWatch::default();
+    let mut next_battery_check = Instant::now() + BATTERY_REFRESH_INTERVAL;
+    warn_low_battery(&mut low_battery, tray_manager.bluetooth_devices(), settings.low_battery_threshold);
+
+    // One tray refresh per burst of audio notifications
+    let mut audio_refresh = Coalescer::new(settings.audio_refresh_quiet, AUDIO_REFRESH_MAX_DELAY);
+
     let _ = event_loop.run(move |event, elwt| {
-        elwt.set_control_flow(ControlFlow::Wait);
+        // Wake up for the orchestration timeout / battery refresh / coalesced audio refresh; otherwise sleep until the next event.
+        let battery_wake = tray_manager.bluetooth_devices().iter().any(|d| d.connected).then_some(next_battery_check);
+        let resume_wake = resume_reconnect.as_ref().and_then(ResumeReconnect::next_attempt).filter(|_| connect_flow.is_none());
+        match [connect_flow.as_ref().map(ConnectAndSwitch::deadline), battery_wake, resume_wake, audio_refresh.deadline()].into_iter().flatten().min() {
+            Some(wake) => elwt.set_control_flow(ControlFlow::WaitUntil(wake)),
+            None => elwt.set_control_flow(ControlFlow::Wait),
+        }
         match event {
             Event::WindowEvent { event: WindowEvent::CloseRequested, .. } => { elwt.exit(); },
+            Event::NewEvents(StartCause::ResumeTimeReached { .. }) => {
+                let now = Instant::now();
+                if audio_refresh.take_due(now) {
+                    let _ = event_loop_proxy.send_event(UserEvent::RefreshAudioDevices);
+                }
+                if let Some(flow) = connect_flow.as_mut() {
+                    let step = flow.on_tick(now);
+                    run_connect_step(step, audio.as_ref(), &mut connect_flow, &mut resume_reconnect, &mut default_history, now);
+                }
+                // Due resume reconnect attempt (waits for any running orchestration)
+                let attempt_due = connect_flow.is_none() && resume_reconnect.as_mut().is_some_and(|r| r.take_due_attempt(now));
+                if attempt_due {
+                    if let Some((address, name)) = resume_reconnect.as_ref().map(|r| (r.address(), r.device_name().to_string())) {
+                        log_dbg!("resume: reconnect attempt {} for {name}", resume_reconnect.as_ref().map_or(0, ResumeReconnect::attempts));
+                        let connected = tray_manager.bluetooth_devices().iter().any(|d| d.address == address && d.connected);
+                        connect_and_use(address, &name, Origin::Resume, connected, &tray_manager, audio.as_ref(), &mut connect_flow, &mut resume_reconnect, &mut default_history, &event_loop_proxy);
+                    }
+                }
+                if now >= next_battery_check {
+                    next_battery_check = now + BATTERY_REFRESH_INTERVAL;
+                    if battery::levels_changed(tray_manager.bluetooth_devices()) {
+                        if let Err(_e) = tray_manager.refresh() {
+                            log_dbg!("battery: tray refresh failed: {}", _e);
+                        }
+                        warn_low_battery(&mut low_battery, tray_manager.bluetooth_devices(), settings.low_battery_threshold);
+                    }
+                }
+            },
             Event::UserEvent(user_event) => {
                 match user_event {
                     UserEvent::TrayEvent(tray_event) => {
@@ -110,36 +268,268 @@ fn main() -> AppResult {
                         else if id == exit_id.as_str() { 
                             elwt.exit(); 
                         }
-                        else if let Some(device_idx) = tray_manager.audio_device_index(id) {
-                            // User selected an audio device
-                            if let Some(device) = tray_manager.get_audio_device(device_idx) {
-                                log_dbg!("audio: user selected device: {}", device.name);
-                                match set_default_audio_device(&device.id) {
-                                    Ok(()) => {
-                                        log_dbg!("audio: successfully set default device");
-                                        // Recreate tray to update checkmark
-                                        if let Err(_e) = tray_manager.recreate() {
-                                            log_dbg!("tray: recreate after device switch failed: {_e}");
-                                        } else {
-                                            about_id = tray_manager.about_id().to_string();
-                                            exit_id = tray_manager.exit_id().to_string();
-                                        }
+                        else if id == tray_manager.radio_toggle_id() {
+                            toggle_radio(tray_manager.radio(), tray_manager.radio_state(), event_loop_proxy.clone());
+                        }
+                        else if id == tray_manager.pair_new_id() {
+                            bluetooth::show_pairing_wizard();
+                        }
+                        else if id == tray_manager.scan_id() {
+                            log_dbg!("bluetooth: scanning for nearby devices");
+                            tray_manager.set_discovery(Discovery::Scanning);
+                            if let Err(_e) = tray_manager.refresh() {
+                                log_dbg!("tray: refresh for discovery failed: {_e}");
+                            }
+                            let scan_proxy = event_loop_proxy.clone();
+                            std::thread::spawn(move || {
+                                let result = bluetooth::discover_devices().map_err(|e| e.to_string());
+                                let _ = scan_proxy.send_event(UserEvent::DiscoveryFinished(result));
+                            });
+                        }
+                        else if let Some(device) = tray_manager.nearby_device(id).cloned() {
+                            log_dbg!("bluetooth: pairing {}", device.name);
+                            let pair_proxy = event_loop_proxy.clone();
+                            std::thread::spawn(move || {
+                                let result = bluetooth::pair_device(device.address).map_err(|e| e.to_string());
+                                let _ = pair_proxy.send_event(UserEvent::PairingFinished(device.address, result));
+                            });
+                        }
+                        else if let Some(device) = tray_manager.unpair_device(id).cloned() {
+                            let unpair_proxy = event_loop_proxy.clone();
+                            std::thread::spawn(move || {
+                                let question = format!("Unpair \"{}\"?\n\nThe device must be paired again before it can reconnect.", device.name);
+                                if !confirm(&question) { return; }
+                                if let Err(e) = bluetooth::remove_device(device.address) {
+                                    show_error_message(&format!("Unpair \"{}\":\n{e}", device.name));
+                                }
+                                let _ = unpair_proxy.send_event(UserEvent::RefreshBluetoothDevices);
+                            });
+                        }
+                        else if tray_manager.lock_id() == Some(id) {
+                            if default_lock.is_enabled() {
+                                log_dbg!("lock: released by user");
+                                default_lock.set_enabled(false);
+                            } else if let Some(current) = tray_manager.audio_devices().iter().find(|d| d.is_default) {
+                                // The locked device is the user's latest choice
+                                log_dbg!("lock: locked to {}", current.name);
+                                default_history.record(&current.id);
+                                default_lock.set_enabled(true);
+                            }
+                            tray_manager.set_locked(default_lock.is_enabled());
+                        }
+                        else if let Some((device, action)) = tray_manager.volume_action(id) {
+                            if let Some(volume) = device.volume {
+                                let result = match action {
+                                    VolumeAction::ToggleMute => audio.set_muted(&device.id, !volume.muted),
+                                    VolumeAction::Up => audio.set_volume(&device.id, volume.stepped(true)),
+                                    VolumeAction::Down => audio.set_volume(&device.id, volume.stepped(false)),
+                                };
+                                if let Err(_e) = result { log_dbg!("audio: {action:?} on {} failed: {_e}", device.name); }
+                                tray_manager.refresh_volumes();
+                            }
+                        }
+                        else if let Some((device, format)) = tray_manager.format_choice(id).map(|(d, f)| (d.clone(), f)) {
+                            if let Err(_e) = audio.set_device_format(&device.id, &format) {
+                                log_dbg!("audio: setting format of {} failed: {_e}", device.name);
+                            }
+                            tray_manager.forget_format(&device.id);
+                            // Re-check entries (the clicked one toggled itself) against the new format
+                            if let Err(_e) = tray_manager.refresh() { log_dbg!("tray: refresh failed: {_e}"); }
+                        }
+                        else if let Some((device, enable)) = tray_manager.endpoint_visibility(id) {
+                            // System-wide, like the Sound control panel; the state change event refreshes the tray
+                            if let Err(e) = audio.set_endpoint_enabled(&device.id, enable) {
+                                log_dbg!("audio: {} {} failed: {e}", if enable { "enabling" } else { "disabling" }, device.name);
+                                let title = if enable { "Could not enable device" } else { "Could not disable device" };
+                                notify::show_balloon(title, &format!("{}: {e}", device.display_name()));
+                            }
+                        }
+                        else if let Some(device) = tray_manager.audio_selection(id).and_then(|s| s.first().map(|&(d, _)| d)).filter(|d| !d.is_active()) {
+                            // Disconnected Bluetooth endpoint: connect it and make it the default ("Connect and use")
+                            if let Some(bt_device) = device_for_endpoint(&device.name, tray_manager.bluetooth_devices()).cloned() {
+                                log_dbg!("audio: {} is disconnected -> connect {}", device.name, bt_device.name);
+                                connect_and_use(bt_device.address, &bt_device.name, Origin::User, bt_device.connected, &tray_manager, audio.as_ref(), &mut connect_flow, &mut resume_reconnect, &mut default_history, &event_loop_proxy);
+                            }
+                        }
+                        else if let Some(selection) = tray_manager.audio_selection(id) {
+                            // User selected an output or input device (same IPolicyConfig path); a grouped
+                            // device assigns its stereo and hands-free endpoints separately
+                            let is_output = selection.first().is_some_and(|(d, _)| tray_manager.audio_devices().iter().any(|o| o.id == d.id));
+                            let chosen_output = selection.iter()
+                                .find(|(_, roles)| is_output && roles.contains(&AudioRole::Console))
+                                .map(|(d, _)| d.id.clone());
+                            let result = selection.iter().try_for_each(|(device, roles)| {
+                                log_dbg!("audio: user selected device: {} for {roles:?}", device.name);
+                                audio.set_default_roles(&device.id, roles)
+                            });
+                            match result {
+                                Ok(()) => {
+                                    log_dbg!("audio: successfully set default device");
+                                    if let Some(endpoint_id) = chosen_output { default_history.record(&endpoint_id); }
+                                    // Refresh tray to update checkmark
+                                    if let Err(_e) = tray_manager.refresh() {
+                                        log_dbg!("tray: refresh after device switch failed: {_e}");
                                     }
-                                    Err(_e) => {
-                                        log_dbg!("audio: failed to set default device: {_e}");
+                                }
+                                Err(_e) => {
+                                    log_dbg!("audio: failed to set default device: {_e}");
+                                }
+                            }
+                        }
+                        else if let Some(device_idx) = tray_manager.bluetooth_device_index(id) {
+                            if let Some(device) = tray_manager.get_bluetooth_device(device_idx).cloned() {
+                                if device.is_audio() {
+                                    // Service toggling blocks for seconds; keep the event loop responsive.
+                                    let connect = !device.connected;
+                                    log_dbg!("bluetooth: user toggled {} -> connect={connect}", device.name);
+                                    // A deliberate disconnect should not be undone after the next resume
+                                    if !connect && last_audio_device.as_ref().is_some_and(|(a, _)| *a == device.address) {
+                                        last_audio_device = None;
                                     }
+                                    let bt_proxy = event_loop_proxy.clone();
+                                    std::thread::spawn(move || {
+                                        if let Err(_e) = bluetooth::set_device_connected(device.address, connect) {
+                                            log_dbg!("bluetooth: connection change failed: {_e}");
+                                        }
+                                        let _ = bt_proxy.send_event(UserEvent::RefreshBluetoothDevices);
+                                    });
+                                } else {
+                                    // No generic connect API for non-audio profiles; fall back to the flyout.
+                                    log_dbg!("bluetooth: {} is not an audio device -> open panel", device.name);
+                                    state.on_tray_left_click();
                                 }
                             }
                         }
+                        else if let Some(device_idx) = tray_manager.connect_use_index(id) {
+                            if let Some(device) = tray_manager.get_bluetooth_device(device_idx).cloned() {
+                                log_dbg!("bluetooth: connect and use {} (connected={})", device.name, device.connected);
+                                connect_and_use(device.address, &device.name, Origin::User, device.connected, &tray_manager, audio.as_ref(), &mut connect_flow, &mut resume_reconnect, &mut default_history, &event_loop_proxy);
+                            }
+                        }
                     },
                     UserEvent::WinKHook => { log_dbg!("hook: Win+K intercepted -> toggle"); state.on_win_k(); },
-                    UserEvent::RefreshAudioDevices => {
+                    UserEvent::AudioDeviceChanged(event) => {
+                        if matches!(event, AudioDeviceEvent::Added { .. } | AudioDeviceEvent::Removed { .. } | AudioDeviceEvent::StateChanged { .. }) {
+                            default_history.on_device_change();
+                        }
+                        // The default output went away: restore the user's previous choice instead of Windows' pick
+                        // (with a priority list, auto-selection after the refresh decides instead)
+                        let lost_default = event.departed().filter(|id| tray_manager.audio_devices().iter().any(|d| d.id == *id && d.is_default));
+                        if let Some(lost) = lost_default.filter(|_| settings.restore_previous_default && settings.output_priority.is_empty() && !default_lock.is_enabled()) {
+                            match default_history.restore_after(lost, audio.as_ref()) {
+                                Ok(Some(_device)) => { log_dbg!("audio: default output {lost} gone -> restored {}", _device.name); }
+                                Ok(None) => { log_dbg!("audio: default output {lost} gone, no previous default to restore"); }
+                                Err(_e) => { log_dbg!("audio: restoring previous default failed: {_e}"); }
+                            }
+                        }
+                        if let Some(id) = event.format_changed() { tray_manager.forget_format(id); }
+                        if event.affects_menu() {
+                            log_dbg!("audio: {event} (refresh pending)");
+                            audio_refresh.on_event(Instant::now());
+                        } else {
+                            log_dbg!("audio: ignored ({event})");
+                        }
+                    }
+                    UserEvent::RefreshAudioDevices | UserEvent::RefreshBluetoothDevices => {
                         // Audio device change notification (event-driven, triggered only when devices change)
-                        if let Err(_e) = tray_manager.recreate() {
+                        if let Err(_e) = tray_manager.refresh() {
                             log_dbg!("audio: device list refresh failed: {}", _e);
-                        } else {
-                            about_id = tray_manager.about_id().to_string();
-                            exit_id = tray_manager.exit_id().to_string();
+                        }
+                        let active_ids = tray_manager.active_endpoint_ids();
+                        if active_ids != volume_watched {
+                            _volume_guard = subscribe_volume(audio.as_ref(), event_loop_proxy.clone());
+                            volume_watched = active_ids;
+                        }
+                        if let Some(flow) = connect_flow.as_mut() {
+                            let step = flow.on_endpoints_changed(tray_manager.audio_devices());
+                            run_connect_step(step, audio.as_ref(), &mut connect_flow, &mut resume_reconnect, &mut default_history, Instant::now());
+                        }
+                        if connect_flow.is_none() {
+                            if default_lock.is_enabled() {
+                                enforce_lock(&mut default_lock, &default_history, audio.as_ref(), &mut tray_manager, Instant::now());
+                            } else {
+                                auto_select_output(&settings.output_priority, &default_history, audio.as_ref(), &mut tray_manager);
+                            }
+                        }
+                        warn_low_battery(&mut low_battery, tray_manager.bluetooth_devices(), settings.low_battery_threshold);
+                    }
+                    UserEvent::AudioVolumeChanged => tray_manager.refresh_volumes(),
+                    UserEvent::RadioStateChanged(state) => tray_manager.set_radio_state(state),
+                    UserEvent::Hotkey(HotkeyAction::ToggleRadio) => {
+                        log_dbg!("hotkey: toggle Bluetooth radio");
+                        toggle_radio(tray_manager.radio(), tray_manager.radio_state(), event_loop_proxy.clone());
+                    }
+                    UserEvent::Hotkey(HotkeyAction::CycleOutput) => {
+                        // Same entries and order as the Audio Devices menu (hidden devices skipped, grouped
+                        // endpoints once, roles split like a click on the entry); a single device stays as is
+                        let entries = tray_manager.output_entries();
+                        if let Some(device) = next_device(&entries).filter(|d| !d.is_default).cloned() {
+                            log_dbg!("hotkey: cycle output -> {}", device.name);
+                            let assignments: Vec<(String, &[AudioRole])> = tray_manager.output_assignments(&device.id)
+                                .into_iter().map(|(endpoint, roles)| (endpoint.id.clone(), roles)).collect();
+                            match assignments.iter().try_for_each(|(endpoint_id, roles)| audio.set_default_roles(endpoint_id, roles)) {
+                                Ok(()) => {
+                                    default_history.record(&device.id);
+                                    notify::show_info_balloon("Audio output", device.display_name());
+                                    // Rebuild now so a quick second press continues from the new default
+                                    if let Err(_e) = tray_manager.refresh() {
+                                        log_dbg!("tray: refresh after cycling output failed: {_e}");
+                                    }
+                                }
+                                Err(e) => show_error_message(&format!("Switch audio output to \"{}\":\n{e}", device.name)),
+                            }
+                        }
+                    }
+                    UserEvent::DiscoveryFinished(result) => {
+                        let discovery = match result {
+                            Ok(nearby) => Discovery::Found(nearby),
+                            Err(e) => {
+                                show_error_message(&format!("Scan for nearby devices:\n{e}"));
+                                Discovery::Idle
+                            }
+                        };
+                        tray_manager.set_discovery(discovery);
+                        if let Err(_e) = tray_manager.refresh() {
+                            log_dbg!("tray: refresh after discovery failed: {_e}");
+                        }
+                    }
+                    UserEvent::PairingFinished(address, result) => {
+                        match result {
+                            Ok(()) => tray_manager.remove_nearby(address),
+                            Err(e) => show_error_message(&format!("Pair new device:\n{e}")),
+                        }
+                        if let Err(_e) = tray_manager.refresh() {
+                            log_dbg!("tray: refresh after pairing failed: {_e}");
+                        }
+                    }
+                    UserEvent::ResumedFromSleep => {
+                        if let Some((address, name)) = &last_audio_device {
+                            log_dbg!("resume: scheduling reconnect of {name}");
+                            resume_reconnect = Some(ResumeReconnect::new(*address, name, settings.resume_reconnect_attempts, Instant::now()));
+                        }
+                    }
+                    UserEvent::BluetoothConnectionChanged { address, connected } => {
+                        if connected {
+                            if let Some(device) = tray_manager.bluetooth_devices().iter().find(|d| d.address == address && d.is_audio()) {
+                                last_audio_device = Some((device.address, device.name.clone()));
+                            }
+                        }
+                        // Update in place; unknown (newly paired) devices need a full rebuild
+                        if !tray_manager.set_bluetooth_connected(address, connected) {
+                            if let Err(_e) = tray_manager.refresh() {
+                                log_dbg!("bluetooth: device list refresh failed: {}", _e);
+                            }
+                        }
+                        warn_low_battery(&mut low_battery, tray_manager.bluetooth_devices(), settings.low_battery_threshold);
+                    }
+                    UserEvent::BluetoothConnectFinished(address, result) => {
+                        if let Err(_e) = tray_manager.refresh() {
+                            log_dbg!("bluetooth: device list refresh failed: {}", _e);
+                        }
+                        // Ignore late results from an orchestration that already timed out
+                        if let Some(flow) = connect_flow.as_mut().filter(|f| f.address() == address) {
+                            let step = flow.on_connect_result(result, tray_manager.audio_devices(), Instant::now());
+                            run_connect_step(step, audio.as_ref(), &mut connect_flow, &mut resume_reconnect, &mut default_history, Instant::now());
                         }
                     }
                 }
@@ -150,6 +540,182 @@ fn main() -> AppResult {
     Ok(())
 }
 
+/// Begin a "Connect and use" orchestration; the Bluetooth connection (if needed)
+/// runs on a worker thread and reports back via `BluetoothConnectFinished`.
+fn start_connect_flow(address: u64, device_name: &str, origin: Origin, connected: bool, proxy: &EventLoopProxy<UserEvent>) -> ConnectAndSwitch {
+    if !connected {
+        let bt_proxy = proxy.clone();
+        std::thread::spawn(move || {
+            let result = bluetooth::set_device_connected(address, true).map_err(|e| e.to_string());
+            let _ = bt_proxy.send_event(UserEvent::BluetoothConnectFinished(address, result));
+        });
+    }
+    ConnectAndSwitch::start(address, device_name, origin, connected, Instant::now())
+}
+
+/// "Connect and use" a paired device (menu entry, disconnected endpoint or resume attempt) and run
+/// its first step, since the endpoint may already be active. Refused while another orchestration
+/// runs, so that one's outcome (and a resume attempt's retry bookkeeping) is not lost.
+#[allow(clippy::too_many_arguments)]
+fn connect_and_use(
+    address: u64, device_name: &str, origin: Origin, connected: bool, tray: &tray::TrayManager, audio: &dyn AudioBackend,
+    flow: &mut Option<ConnectAndSwitch>, resume: &mut Option<ResumeReconnect>, history: &mut DefaultHistory, proxy: &EventLoopProxy<UserEvent>,
+) {
+    if let Some(running) = flow.as_ref() {
+        log_dbg!("connect: {device_name} ignored, still connecting {}", running.device_name());
+        notify::show_balloon("Connect and use", &format!("Still connecting \"{}\"; try again when it has finished.", running.device_name()));
+        return;
+    }
+    let mut started = start_connect_flow(address, device_name, origin, connected, proxy);
+    let step = started.on_endpoints_changed(tray.audio_devices());
+    *flow = Some(started);
+    run_connect_step(step, audio, flow, resume, history, Instant::now());
+}
+
+/// Endpoint IDs, names and types, for `alias.<id>` / `hide.<id>` / `hide_type` settings.
+fn print_audio_devices(audio: &dyn AudioBackend) -> AppResult {
+    for flow in [AudioFlow::Output, AudioFlow::Input] {
+        for device in audio.enumerate(flow, true)? {
+            let kind = format!("{}/{}", device.connection.name(), device.form_factor.name());
+            let description = device.description.as_deref().unwrap_or("-");
+            println!("{flow:?}\t{:?}\t{kind}\t{}\t{}\t{description}", device.state, device.id, device.name);
+        }
+    }
+    Ok(())
+}
+
+/// Watch endpoint volumes; a failure only costs live volume updates in the menu.
+fn subscribe_volume(audio: &dyn AudioBackend, proxy: EventLoopProxy<UserEvent>) -> Option<Subscription> {
+    audio.subscribe_volume(Arc::new(move || { let _ = proxy.send_event(UserEvent::AudioVolumeChanged); }))
+        .map_err(|_e| { log_dbg!("audio: volume notifications unavailable: {_e}"); })
+        .ok()
+}
+
+/// Execute a "Connect and use" step and, once the orchestration is finished,
+/// clear it and report the outcome. Failures of resume reconnect attempts stay
+/// quiet until the retry policy gives up; a manual "Connect and use" of the same
+/// headset leaves a pending retry schedule alone.
+fn run_connect_step(step: Step, audio: &dyn AudioBackend, flow: &mut Option<ConnectAndSwitch>, resume: &mut Option<ResumeReconnect>, history: &mut DefaultHistory, now: Instant) {
+    let outcome = match step {
+        Step::Pending => return,
+        Step::SetDefault(endpoint_id) => audio.set_default_all_roles(&endpoint_id)
+            .map(|()| history.record(&endpoint_id))
+            .map_err(|e| format!("Failed to switch audio output: {e}")),
+        Step::Failed(failure) => Err(failure.to_string()),
+    };
+    let Some(finished) = flow.take() else { return };
+    let device_name = finished.device_name();
+    let from_resume = finished.origin() == Origin::Resume;
+    match outcome {
+        Ok(()) => {
+            log_dbg!("connect: {device_name} connected and set as default output");
+            if from_resume { *resume = None; }
+        }
+        Err(e) if from_resume => {
+            let decision = resume.as_mut().map_or(RetryDecision::GiveUp, |r| r.on_attempt_failed(now));
+            match decision {
+                RetryDecision::RetryAt(_) => { log_dbg!("resume: reconnect of {device_name} failed ({e}); retrying"); }
+                RetryDecision::GiveUp => {
+                    let attempts = resume.as_ref().map_or(0, ResumeReconnect::attempts);
+                    *resume = None;
+                    show_error_message(&format!("Reconnect \"{device_name}\" after resume:\n{e}\n(gave up after {attempts} attempt(s))"));
+                }
+            }
+        }
+        Err(e) => {
+            log_dbg!("connect: {device_name} failed: {e}");
+            show_error_message(&format!("Connect and use \"{device_name}\":\n{e}"));
+        }
+    }
+}
+
+/// Keep the default output on the highest-ranked active device of `output_priority`,
+/// unless the user picked an output since the last device change.
+fn auto_select_output(priority: &OutputPriority, history: &DefaultHistory, audio: &dyn AudioBackend, tray: &mut tray::TrayManager) {
+    if history.user_override() { return; }
+    let Some(device) = priority.select(tray.audio_devices()).cloned() else { return };
+    log_dbg!("audio: output priority -> {}", device.name);
+    // Console + Multimedia only, so a separately chosen communications device stays
+    match audio.set_default_roles(&device.id, &AudioRole::MEDIA) {
+        Ok(()) => {
+            if let Err(_e) = tray.refresh() {
+                log_dbg!("tray: refresh after auto-selection failed: {_e}");
+            }
+        }
+        Err(_e) => { log_dbg!("audio: auto-selecting {} failed: {_e}", device.name); }
+    }
+}
+
+/// Revert a default output change made outside the app while the lock is on (the locked
+/// device must be active); releases the lock with a warning when the revert limit is hit.
+fn enforce_lock(lock: &mut DefaultLock, history: &DefaultHistory, audio: &dyn AudioBackend, tray: &mut tray::TrayManager, now: Instant) {
+    let Some(locked) = history.latest() else { return };
+    let Some(device) = tray.audio_devices().iter().find(|d| d.id == locked && d.is_active() && !d.is_default).cloned() else { return };
+    if !lock.allow_revert(now) {
+        log_dbg!("lock: revert limit reached, lock on {} released", device.name);
+        tray.set_locked(false);
+        notify::show_balloon("Default device lock released", &format!("Another program keeps changing the default output away from \"{}\".", device.display_name()));
+        return;
+    }
+    log_dbg!("lock: default output changed externally -> reverting to {}", device.name);
+    match audio.set_default_roles(&device.id, &AudioRole::MEDIA) {
+        Ok(()) => {
+            if let Err(_e) = tray.refresh() {
+                log_dbg!("tray: refresh after lock revert failed: {_e}");
+            }
+        }
+        Err(_e) => { log_dbg!("lock: reverting to {} failed: {_e}", device.name); }
+    }
+}
+
+/// Flip the Bluetooth radio off the UI thread (WinRT calls block); the tray refreshes when done.
+fn toggle_radio(radio: Arc<dyn RadioControl>, shown: RadioState, proxy: EventLoopProxy<UserEvent>) {
+    let Some(on) = radio::toggle_target(shown) else {
+        log_dbg!("radio: toggle ignored (radio unavailable)");
+        return;
+    };
+    std::thread::spawn(move || {
+        if let Err(e) = radio.set_enabled(on) { show_error_message(&format!("Bluetooth radio:\n{e}")); }
+        let _ = proxy.send_event(UserEvent::RadioStateChanged(radio::current_state(radio.as_ref())));
+        let _ = proxy.send_event(UserEvent::RefreshBluetoothDevices);
+    });
+}
+
+/// Read the radio state off the UI thread and report it to the tray.
+fn report_radio_state(radio: Arc<dyn RadioControl>, proxy: EventLoopProxy<UserEvent>) {
+    std::thread::spawn(move || {
+        let _ = proxy.send_event(UserEvent::RadioStateChanged(radio::current_state(radio.as_ref())));
+    });
+}
+
+/// Balloon for devices that just crossed the configured low-battery threshold.
+fn warn_low_battery(watch: &mut LowBatteryWatch, devices: &[BluetoothDevice], threshold: Option<u8>) {
+    let Some(threshold) = threshold else { return };
+    for device in watch.check(devices, threshold) {
+        notify::show_balloon("Low Bluetooth battery", &format!("{} is at {}%", device.name, device.battery.unwrap_or(0)));
+    }
+}
+
+/// Yes/No question box; blocks the calling thread (call from a worker thread).
+fn confirm(text: &str) -> bool {
+    use windows_sys::Win32::UI::WindowsAndMessaging::{MessageBoxW, MB_YESNO, MB_ICONQUESTION, IDYES};
+    use crate::utf16_strings::encode_utf16_null;
+    let text = encode_utf16_null(text);
+    let title = encode_utf16_null("ShowBTConnect");
+    unsafe { MessageBoxW(std::ptr::null_mut(), text.as_ptr(), title.as_ptr(), MB_YESNO | MB_ICONQUESTION) == IDYES }
+}
+
+/// Non-blocking warning box (own thread so the event loop keeps running).
+fn show_error_message(text: &str) {
+    use windows_sys::Win32::UI::WindowsAndMessaging::{MessageBoxW, MB_OK, MB_ICONWARNING};
+    use crate::utf16_strings::encode_utf16_null;
+    let text = encode_utf16_null(text);
+    std::thread::spawn(move || {
+        let title = encode_utf16_null("ShowBTConnect");
+        unsafe { MessageBoxW(std::ptr::null_mut(), text.as_ptr(), title.as_ptr(), MB_OK | MB_ICONWARNING); }
+    });
+}
+
 fn hide_console_window() {
     #[cfg(not(debug_assertions))]
     unsafe {
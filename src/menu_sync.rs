@@ -0,0 +1,234 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2025 Triffit
+
+//! In-place menu updates. The tray menu is built once; device lists are synced
+//! into it by key (endpoint ID, Bluetooth address), reusing the item of every
+//! key that stays listed, so menu IDs are stable and an open menu is not closed.
+
+use tray_icon::menu::{CheckMenuItem, IsMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu};
+use crate::config::AppResult;
+
+/// Menus and submenus: things items can be inserted into and removed from.
+pub trait Container {
+    fn insert_at(&self, item: &dyn IsMenuItem, position: usize) -> AppResult;
+    fn remove_item(&self, item: &dyn IsMenuItem) -> AppResult;
+}
+
+impl Container for Menu {
+    fn insert_at(&self, item: &dyn IsMenuItem, position: usize) -> AppResult { Ok(self.insert(item, position)?) }
+    fn remove_item(&self, item: &dyn IsMenuItem) -> AppResult { Ok(self.remove(item)?) }
+}
+
+impl Container for Submenu {
+    fn insert_at(&self, item: &dyn IsMenuItem, position: usize) -> AppResult { Ok(self.insert(item, position)?) }
+    fn remove_item(&self, item: &dyn IsMenuItem) -> AppResult { Ok(self.remove(item)?) }
+}
+
+/// Something occupying one slot of a container (a plain item, or a group headed by a submenu).
+pub trait Entry {
+    fn slot(&self) -> &dyn IsMenuItem;
+}
+
+impl Entry for MenuItem { fn slot(&self) -> &dyn IsMenuItem { self } }
+impl Entry for CheckMenuItem { fn slot(&self) -> &dyn IsMenuItem { self } }
+impl Entry for Submenu { fn slot(&self) -> &dyn IsMenuItem { self } }
+impl Entry for PredefinedMenuItem { fn slot(&self) -> &dyn IsMenuItem { self } }
+
+/// Items keyed by what they show, at positions `0..len` of a container, in display order.
+pub struct KeyedItems<K, T> {
+    entries: Vec<(K, T)>,
+}
+
+impl<K, T> Default for KeyedItems<K, T> {
+    fn default() -> Self { Self { entries: Vec::new() } }
+}
+
+impl<K: PartialEq, T: Entry> KeyedItems<K, T> {
+    /// Make the container show one item per element of `wanted`, in that order:
+    /// items of vanished keys are removed, moved keys are re-inserted (same item),
+    /// new keys get `create`d. `update` then applies text / state to every item.
+    pub fn sync<D>(
+        &mut self, container: &dyn Container, wanted: &[D],
+        key: impl Fn(&D) -> K, create: impl Fn() -> AppResult<T>, update: impl Fn(&T, &D),
+    ) -> AppResult {
+        let keys: Vec<K> = wanted.iter().map(key).collect();
+        self.entries.retain(|(k, item)| {
+            let keep = keys.contains(k);
+            if !keep { let _ = container.remove_item(item.slot()); }
+            keep
+        });
+        // Every remaining key is wanted, so an existing key is never found before `position`
+        for (position, (k, data)) in keys.into_iter().zip(wanted).enumerate() {
+            match self.entries.iter().position(|(existing, _)| *existing == k) {
+                Some(found) if found == position => {}
+                Some(found) => {
+                    let entry = self.entries.remove(found);
+                    container.remove_item(entry.1.slot())?;
+                    container.insert_at(entry.1.slot(), position)?;
+                    self.entries.insert(position, entry);
+                }
+                None => {
+                    let item = create()?;
+                    container.insert_at(item.slot(), position)?;
+                    self.entries.insert(position, (k, item));
+                }
+            }
+            update(&self.entries[position].1, data);
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, key: &K) -> Option<&T> {
+        self.entries.iter().find(|(k, _)| k == key).map(|(_, item)| item)
+    }
+
+    /// Key of the item with this menu ID.
+    pub fn key_for(&self, menu_id: &str) -> Option<&K> {
+        self.entries.iter().find(|(_, item)| item.slot().id().0 == menu_id).map(|(k, _)| k)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(K, T)> { self.entries.iter() }
+}
+
+/// An item that is part of its container only while wanted.
+pub struct Optional<T> {
+    pub item: T,
+    shown: bool,
+}
+
+impl<T: Entry> Optional<T> {
+    pub fn hidden(item: T) -> Self { Self { item, shown: false } }
+
+    /// Insert at / remove from `*position`; advances `position` past the item while shown.
+    pub fn show(&mut self, container: &dyn Container, wanted: bool, position: &mut usize) -> AppResult {
+        if wanted != self.shown {
+            if wanted { container.insert_at(self.item.slot(), *position)?; } else { container.remove_item(self.item.slot())?; }
+            self.shown = wanted;
+        }
+        if self.shown { *position += 1; }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    /// Container that keeps the menu IDs of its slots, in order, and counts changes.
+    #[derive(Default)]
+    struct Recorder {
+        slots: RefCell<Vec<String>>,
+        changes: RefCell<usize>,
+    }
+
+    impl Container for Recorder {
+        fn insert_at(&self, item: &dyn IsMenuItem, position: usize) -> AppResult {
+            let mut slots = self.slots.borrow_mut();
+            if position > slots.len() { return Err(format!("insert at {position} of {}", slots.len()).into()); }
+            slots.insert(position, item.id().0.clone());
+            *self.changes.borrow_mut() += 1;
+            Ok(())
+        }
+        fn remove_item(&self, item: &dyn IsMenuItem) -> AppResult {
+            let mut slots = self.slots.borrow_mut();
+            let position = slots.iter().position(|id| *id == item.id().0).ok_or("item not in container")?;
+            slots.remove(position);
+            *self.changes.borrow_mut() += 1;
+            Ok(())
+        }
+    }
+
+    impl Recorder {
+        fn take_changes(&self) -> usize { std::mem::take(&mut *self.changes.borrow_mut()) }
+    }
+
+    fn sync(items: &mut KeyedItems<&'static str, MenuItem>, container: &Recorder, wanted: &[&'static str]) {
+        items.sync(container, wanted, |key| *key, || Ok(MenuItem::new("", true, None)), |item, key| item.set_text(*key)).unwrap();
+    }
+
+    fn id(items: &KeyedItems<&'static str, MenuItem>, key: &'static str) -> String { items.get(&key).unwrap().id().0.clone() }
+
+    /// Keys of the container's slots, in order (via the items' IDs).
+    fn shown(items: &KeyedItems<&'static str, MenuItem>, container: &Recorder) -> Vec<&'static str> {
+        container.slots.borrow().iter().map(|slot| *items.iter().find(|(_, item)| item.id().0 == *slot).map(|(k, _)| k).unwrap()).collect()
+    }
+
+    #[test]
+    fn new_keys_are_inserted_in_order() {
+        let (container, mut items) = (Recorder::default(), KeyedItems::default());
+        sync(&mut items, &container, &["a", "b", "c"]);
+        assert_eq!(shown(&items, &container), ["a", "b", "c"]);
+        assert_eq!(items.get(&"b").unwrap().text(), "b");
+        sync(&mut items, &container, &["a", "x", "b", "c", "y"]);
+        assert_eq!(shown(&items, &container), ["a", "x", "b", "c", "y"]);
+    }
+
+    #[test]
+    fn listed_keys_keep_their_items() {
+        let (container, mut items) = (Recorder::default(), KeyedItems::default());
+        sync(&mut items, &container, &["a", "b", "c"]);
+        let (b, c) = (id(&items, "b"), id(&items, "c"));
+        container.take_changes();
+        sync(&mut items, &container, &["a", "b", "c"]);
+        assert_eq!(container.take_changes(), 0);
+        sync(&mut items, &container, &["b", "new", "c"]);
+        assert_eq!((id(&items, "b"), id(&items, "c")), (b.clone(), c));
+        assert_eq!(items.key_for(&b), Some(&"b"));
+        assert_eq!(shown(&items, &container), ["b", "new", "c"]);
+    }
+
+    #[test]
+    fn vanished_keys_are_removed() {
+        let (container, mut items) = (Recorder::default(), KeyedItems::default());
+        sync(&mut items, &container, &["a", "b", "c"]);
+        let a = id(&items, "a");
+        sync(&mut items, &container, &["b"]);
+        assert_eq!(shown(&items, &container), ["b"]);
+        assert_eq!(items.key_for(&a), None);
+        sync(&mut items, &container, &[]);
+        assert!(container.slots.borrow().is_empty());
+    }
+
+    #[test]
+    fn reorder_moves_only_displaced_items() {
+        let (container, mut items) = (Recorder::default(), KeyedItems::default());
+        sync(&mut items, &container, &["a", "b", "c"]);
+        let ids: Vec<String> = ["a", "b", "c"].into_iter().map(|k| id(&items, k)).collect();
+        container.take_changes();
+        sync(&mut items, &container, &["c", "a", "b"]);
+        assert_eq!(shown(&items, &container), ["c", "a", "b"]);
+        // "c" is removed and re-inserted; "a" and "b" end up in place without being touched
+        assert_eq!(container.take_changes(), 2);
+        assert_eq!(["a", "b", "c"].into_iter().map(|k| id(&items, k)).collect::<Vec<_>>(), ids);
+    }
+
+    #[test]
+    fn optional_items_advance_a_running_position() {
+        let (container, mut items) = (Recorder::default(), KeyedItems::default());
+        sync(&mut items, &container, &["a", "b"]);
+        let mut first = Optional::hidden(MenuItem::new("first", true, None));
+        let mut second = Optional::hidden(MenuItem::new("second", true, None));
+        let slots = |shown: &[&MenuItem]| shown.iter().map(|item| item.id().0.clone()).collect::<Vec<_>>();
+
+        let mut position = 2;
+        first.show(&container, false, &mut position).unwrap();
+        second.show(&container, true, &mut position).unwrap();
+        assert_eq!(position, 3);
+        assert_eq!(container.slots.borrow()[2..], slots(&[&second.item]));
+
+        // Showing the first one puts it ahead of the second
+        let mut position = 2;
+        first.show(&container, true, &mut position).unwrap();
+        second.show(&container, true, &mut position).unwrap();
+        assert_eq!(position, 4);
+        assert_eq!(container.slots.borrow()[2..], slots(&[&first.item, &second.item]));
+
+        container.take_changes();
+        let mut position = 2;
+        first.show(&container, false, &mut position).unwrap();
+        second.show(&container, true, &mut position).unwrap();
+        assert_eq!((position, container.take_changes()), (3, 1));
+        assert_eq!(container.slots.borrow()[2..], slots(&[&second.item]));
+    }
+}
@@ -0,0 +1,60 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2025 Triffit
+
+//! Balloon (toast) notifications.
+//! `tray-icon` does not expose `NIF_INFO`, so a short-lived helper icon owned by
+//! a message-only window carries the balloon and is removed afterwards.
+
+use windows_sys::Win32::UI::Shell::{
+    Shell_NotifyIconW, NOTIFYICONDATAW, NIM_ADD, NIM_DELETE, NIF_ICON, NIF_INFO, NIF_TIP, NIIF_INFO, NIIF_NOSOUND, NIIF_WARNING,
+    NOTIFY_ICON_INFOTIP_FLAGS,
+};
+use windows_sys::Win32::UI::WindowsAndMessaging::{CreateWindowExW, DestroyWindow, LoadIconW, HWND_MESSAGE};
+use windows_sys::Win32::System::LibraryLoader::GetModuleHandleW;
+use crate::config::BALLOON_DURATION;
+use crate::log_dbg;
+use crate::utf16_strings::{encode_utf16, encode_utf16_null};
+
+/// Show a warning balloon without blocking the caller.
+pub fn show_balloon(title: &str, text: &str) { show(title, text, NIIF_WARNING); }
+
+/// Show a silent informational balloon (e.g. the device chosen by a hotkey).
+pub fn show_info_balloon(title: &str, text: &str) { show(title, text, NIIF_INFO | NIIF_NOSOUND); }
+
+fn show(title: &str, text: &str, info_flags: NOTIFY_ICON_INFOTIP_FLAGS) {
+    let title = encode_utf16(title);
+    let text = encode_utf16(text);
+    std::thread::spawn(move || unsafe {
+        let hinst = GetModuleHandleW(std::ptr::null());
+        let class = encode_utf16_null("STATIC");
+        let hwnd = CreateWindowExW(0, class.as_ptr(), std::ptr::null(), 0, 0, 0, 0, 0, HWND_MESSAGE, std::ptr::null_mut(), hinst, std::ptr::null());
+        if hwnd.is_null() {
+            log_dbg!("notify: helper window creation failed");
+            return;
+        }
+        let mut nid: NOTIFYICONDATAW = std::mem::zeroed();
+        nid.cbSize = std::mem::size_of::<NOTIFYICONDATAW>() as u32;
+        nid.hWnd = hwnd;
+        nid.uID = 1;
+        nid.uFlags = NIF_ICON | NIF_TIP | NIF_INFO;
+        nid.hIcon = LoadIconW(hinst, 1 as *const u16);
+        nid.dwInfoFlags = info_flags;
+        copy_truncated(&mut nid.szTip, &title);
+        copy_truncated(&mut nid.szInfoTitle, &title);
+        copy_truncated(&mut nid.szInfo, &text);
+        if Shell_NotifyIconW(NIM_ADD, &nid) == 0 {
+            log_dbg!("notify: Shell_NotifyIconW(NIM_ADD) failed");
+        } else {
+            std::thread::sleep(BALLOON_DURATION);
+            Shell_NotifyIconW(NIM_DELETE, &nid);
+        }
+        DestroyWindow(hwnd);
+    });
+}
+
+/// Copy into a fixed NOTIFYICONDATAW buffer, always leaving a terminating null.
+fn copy_truncated(dst: &mut [u16], src: &[u16]) {
+    let n = src.len().min(dst.len() - 1);
+    dst[..n].copy_from_slice(&src[..n]);
+    dst[n] = 0;
+}
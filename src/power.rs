@@ -0,0 +1,63 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2025 Triffit
+
+//! Resume-from-sleep notification.
+//! `WM_POWERBROADCAST` is only sent to top-level windows, and the app has none:
+//! the tray icon's hidden window belongs to `tray-icon` and winit does not hand
+//! out raw messages. `PowerRegisterSuspendResumeNotification` delivers the same
+//! `PBT_*` events through a callback instead; only `PBT_APMRESUMEAUTOMATIC`
+//! (fires for every resume, user present or not) is forwarded.
+
+use std::ffi::c_void;
+use std::sync::Arc;
+use windows_sys::Win32::System::Power::{
+    PowerRegisterSuspendResumeNotification, PowerUnregisterSuspendResumeNotification,
+    DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS,
+};
+use windows_sys::Win32::UI::WindowsAndMessaging::{DEVICE_NOTIFY_CALLBACK, HPOWERNOTIFY, PBT_APMRESUMEAUTOMATIC};
+use crate::config::AppResult;
+use crate::log_dbg;
+
+pub type ResumeCallback = Arc<dyn Fn() + Send + Sync>;
+
+/// Keeps the registration alive; unregisters on drop.
+pub struct PowerGuard {
+    registration: HPOWERNOTIFY,
+    // Must outlive the registration: the system keeps pointers to both.
+    _params: Box<DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS>,
+    context: *mut ResumeCallback,
+}
+
+impl Drop for PowerGuard {
+    fn drop(&mut self) {
+        unsafe {
+            PowerUnregisterSuspendResumeNotification(self.registration);
+            drop(Box::from_raw(self.context));
+        }
+    }
+}
+
+unsafe extern "system" fn on_power_event(context: *const c_void, event: u32, _setting: *const c_void) -> u32 {
+    if event == PBT_APMRESUMEAUTOMATIC {
+        log_dbg!("power: resumed from sleep");
+        let callback = &*context.cast::<ResumeCallback>();
+        callback();
+    }
+    0
+}
+
+/// Invoke `callback` (on a system thread) after every resume from sleep / hibernate.
+pub fn register_resume_callback(callback: ResumeCallback) -> AppResult<PowerGuard> {
+    let context = Box::into_raw(Box::new(callback));
+    let params = Box::new(DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS { Callback: Some(on_power_event), Context: context.cast() });
+    let mut registration: HPOWERNOTIFY = std::ptr::null_mut();
+    let err = unsafe {
+        PowerRegisterSuspendResumeNotification(DEVICE_NOTIFY_CALLBACK, std::ptr::from_ref(params.as_ref()).cast_mut().cast(), &mut registration)
+    };
+    if err != 0 {
+        unsafe { drop(Box::from_raw(context)); }
+        return Err(format!("Failed to register for resume notifications (error {err})").into());
+    }
+    log_dbg!("power: registered resume notification");
+    Ok(PowerGuard { registration, _params: params, context })
+}
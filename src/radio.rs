@@ -0,0 +1,242 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2025 Triffit
+
+//! Bluetooth radio on/off control.
+//! Hardware access sits behind `RadioControl` (Windows.Devices.Radios in
+//! production); the tray entry is derived purely from `RadioState`, so the menu
+//! model does not depend on a real radio. WinRT calls block, so the tray shows a
+//! cached state that workers and `StateChanged` notifications keep current.
+
+use std::sync::Arc;
+use crate::config::AppResult;
+use crate::log_dbg;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RadioState {
+    On,
+    Off,
+    /// No radio, radio disabled in Device Manager, or access denied.
+    Unavailable,
+}
+
+/// Callback type for radio state changes (made by the app, Windows settings or the airplane mode).
+pub type RadioStateCallback = Arc<dyn Fn(RadioState) + Send + Sync>;
+
+/// Keep-alive token for a state subscription.
+pub type RadioSubscription = Box<dyn std::any::Any>;
+
+pub trait RadioControl: Send + Sync {
+    fn state(&self) -> AppResult<RadioState>;
+    fn set_enabled(&self, on: bool) -> AppResult;
+    /// Invoke `callback` (on a system thread) whenever the radio turns on or off.
+    fn subscribe(&self, callback: RadioStateCallback) -> AppResult<RadioSubscription>;
+}
+
+/// Tray entry for the radio toggle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RadioMenuModel {
+    pub text: &'static str,
+    pub checked: bool,
+    pub enabled: bool,
+}
+
+impl RadioMenuModel {
+    pub fn from_state(state: RadioState) -> Self {
+        match state {
+            RadioState::On => Self { text: "Bluetooth radio: On", checked: true, enabled: true },
+            RadioState::Off => Self { text: "Bluetooth radio: Off", checked: false, enabled: true },
+            RadioState::Unavailable => Self { text: "Bluetooth radio: unavailable", checked: false, enabled: false },
+        }
+    }
+}
+
+/// Desired radio power when the toggle is activated (None if it cannot be toggled).
+pub fn toggle_target(state: RadioState) -> Option<bool> {
+    match state {
+        RadioState::On => Some(false),
+        RadioState::Off => Some(true),
+        RadioState::Unavailable => None,
+    }
+}
+
+/// Read the state, treating errors as unavailable (menu must still build).
+pub fn current_state(radio: &dyn RadioControl) -> RadioState {
+    radio.state().unwrap_or_else(|_e| {
+        log_dbg!("radio: state query failed: {_e}");
+        RadioState::Unavailable
+    })
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+// Windows.Devices.Radios implementation
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[cfg(not(feature = "fake-backends"))]
+pub use winrt::WinRtRadio;
+
+#[cfg(not(feature = "fake-backends"))]
+mod winrt {
+    use super::{RadioControl, RadioState, RadioStateCallback, RadioSubscription};
+    use crate::config::AppResult;
+    use crate::log_dbg;
+    use windows::Devices::Radios::{Radio, RadioAccessStatus, RadioKind, RadioState as WinRadioState};
+    use windows::Foundation::TypedEventHandler;
+
+    /// Looks the Bluetooth radio up on every call (radios come and go with USB dongles).
+    pub struct WinRtRadio;
+
+    impl WinRtRadio {
+        fn bluetooth_radio() -> AppResult<Option<Radio>> {
+            let radios = Radio::GetRadiosAsync()?.get()?;
+            for radio in radios {
+                if radio.Kind()? == RadioKind::Bluetooth { return Ok(Some(radio)); }
+            }
+            Ok(None)
+        }
+    }
+
+    fn state_of(radio: &Radio) -> RadioState {
+        match radio.State() {
+            Ok(WinRadioState::On) => RadioState::On,
+            Ok(WinRadioState::Off) => RadioState::Off,
+            _ => RadioState::Unavailable,
+        }
+    }
+
+    /// `StateChanged` registration; removed on drop.
+    struct StateWatch {
+        radio: Radio,
+        token: i64,
+    }
+
+    impl Drop for StateWatch {
+        fn drop(&mut self) { let _ = self.radio.RemoveStateChanged(self.token); }
+    }
+
+    impl RadioControl for WinRtRadio {
+        fn state(&self) -> AppResult<RadioState> {
+            Ok(Self::bluetooth_radio()?.map_or(RadioState::Unavailable, |radio| state_of(&radio)))
+        }
+
+        fn set_enabled(&self, on: bool) -> AppResult {
+            let radio = Self::bluetooth_radio()?.ok_or("No Bluetooth radio found")?;
+            // Without consent (Settings > Privacy > Radios) SetStateAsync is denied
+            let access = Radio::RequestAccessAsync()?.get()?;
+            if access != RadioAccessStatus::Allowed {
+                return Err(format!("Bluetooth radio access denied ({access:?})").into());
+            }
+            let target = if on { WinRadioState::On } else { WinRadioState::Off };
+            let status = radio.SetStateAsync(target)?.get()?;
+            if status != RadioAccessStatus::Allowed {
+                return Err(format!("Bluetooth radio access denied ({status:?})").into());
+            }
+            log_dbg!("radio: set {}", if on { "on" } else { "off" });
+            Ok(())
+        }
+
+        /// Watches the radio present now; a dongle plugged in later is picked up by the next toggle.
+        fn subscribe(&self, callback: RadioStateCallback) -> AppResult<RadioSubscription> {
+            let Some(radio) = Self::bluetooth_radio()? else { return Ok(Box::new(())) };
+            let watched = radio.clone();
+            let token = radio.StateChanged(&TypedEventHandler::new(move |_, _| {
+                callback(state_of(&watched));
+                Ok(())
+            }))?;
+            log_dbg!("radio: watching state changes");
+            Ok(Box::new(StateWatch { radio, token }))
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+// In-memory fake (`--features fake-backends`)
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[cfg(any(test, feature = "fake-backends"))]
+pub use fake::FakeRadio;
+
+#[cfg(any(test, feature = "fake-backends"))]
+mod fake {
+    use super::{RadioControl, RadioState, RadioStateCallback, RadioSubscription};
+    use crate::config::AppResult;
+    use std::sync::Mutex;
+
+    /// Radio that simply remembers the last requested state and notifies subscribers.
+    pub struct FakeRadio {
+        state: Mutex<RadioState>,
+        subscribers: Mutex<Vec<RadioStateCallback>>,
+    }
+
+    impl FakeRadio {
+        pub fn with_state(state: RadioState) -> Self {
+            Self { state: Mutex::new(state), subscribers: Mutex::new(Vec::new()) }
+        }
+    }
+
+    impl Default for FakeRadio {
+        fn default() -> Self { Self::with_state(RadioState::On) }
+    }
+
+    impl RadioControl for FakeRadio {
+        fn state(&self) -> AppResult<RadioState> { Ok(*self.state.lock().unwrap()) }
+        fn set_enabled(&self, on: bool) -> AppResult {
+            let new_state = {
+                let mut state = self.state.lock().unwrap();
+                if *state == RadioState::Unavailable { return Err("Fake radio unavailable".into()); }
+                *state = if on { RadioState::On } else { RadioState::Off };
+                *state
+            };
+            let subscribers = self.subscribers.lock().unwrap().clone();
+            for callback in subscribers { callback(new_state); }
+            Ok(())
+        }
+        fn subscribe(&self, callback: RadioStateCallback) -> AppResult<RadioSubscription> {
+            self.subscribers.lock().unwrap().push(callback);
+            Ok(Box::new(()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn menu_model_follows_state() {
+        assert_eq!(RadioMenuModel::from_state(RadioState::On), RadioMenuModel { text: "Bluetooth radio: On", checked: true, enabled: true });
+        assert_eq!(RadioMenuModel::from_state(RadioState::Off), RadioMenuModel { text: "Bluetooth radio: Off", checked: false, enabled: true });
+        let unavailable = RadioMenuModel::from_state(RadioState::Unavailable);
+        assert!(!unavailable.enabled && !unavailable.checked);
+    }
+
+    #[test]
+    fn toggle_target_flips_power() {
+        assert_eq!(toggle_target(RadioState::On), Some(false));
+        assert_eq!(toggle_target(RadioState::Off), Some(true));
+        assert_eq!(toggle_target(RadioState::Unavailable), None);
+    }
+
+    #[test]
+    fn toggling_notifies_subscribers() {
+        let radio = FakeRadio::default();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&seen);
+        let _subscription = radio.subscribe(Arc::new(move |state| sink.lock().unwrap().push(state))).unwrap();
+
+        for _ in 0..2 {
+            let on = toggle_target(current_state(&radio)).unwrap();
+            radio.set_enabled(on).unwrap();
+        }
+        assert_eq!(*seen.lock().unwrap(), vec![RadioState::Off, RadioState::On]);
+        assert!(RadioMenuModel::from_state(current_state(&radio)).checked);
+    }
+
+    #[test]
+    fn unavailable_radio_is_not_toggled() {
+        let radio = FakeRadio::with_state(RadioState::Unavailable);
+        assert_eq!(toggle_target(current_state(&radio)), None);
+        assert!(radio.set_enabled(true).is_err());
+        assert_eq!(current_state(&radio), RadioState::Unavailable);
+    }
+}
@@ -0,0 +1,119 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2025 Triffit
+
+//! Opt-in reconnect of the last used Bluetooth headset after resume from sleep.
+//! Retry / backoff bookkeeping only: each attempt is a regular "Connect and use"
+//! orchestration started by the event loop. Time is always passed in as `now`,
+//! so the schedule can be exercised with a synthetic clock.
+
+use std::time::{Duration, Instant};
+use crate::config::{RESUME_RETRY_BASE_DELAY, RESUME_RETRY_MAX_DELAY, RESUME_SETTLE_DELAY};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryDecision {
+    RetryAt(Instant),
+    GiveUp,
+}
+
+#[derive(Debug)]
+pub struct ResumeReconnect {
+    address: u64,
+    device_name: String,
+    max_attempts: u32,
+    attempts: u32,
+    /// Next scheduled attempt; None while an attempt is in flight.
+    next_attempt: Option<Instant>,
+}
+
+impl ResumeReconnect {
+    /// First attempt waits for the Bluetooth stack to come back after resume.
+    pub fn new(address: u64, device_name: &str, max_attempts: u32, now: Instant) -> Self {
+        Self {
+            address,
+            device_name: device_name.to_string(),
+            max_attempts: max_attempts.max(1),
+            attempts: 0,
+            next_attempt: Some(now + RESUME_SETTLE_DELAY),
+        }
+    }
+
+    pub fn address(&self) -> u64 { self.address }
+    pub fn device_name(&self) -> &str { &self.device_name }
+    pub fn attempts(&self) -> u32 { self.attempts }
+    pub fn next_attempt(&self) -> Option<Instant> { self.next_attempt }
+
+    /// Claim the scheduled attempt if it is due; the caller then starts it.
+    pub fn take_due_attempt(&mut self, now: Instant) -> bool {
+        if !self.next_attempt.is_some_and(|at| now >= at) { return false; }
+        self.attempts += 1;
+        self.next_attempt = None;
+        true
+    }
+
+    /// The in-flight attempt failed: schedule the next one or give up.
+    pub fn on_attempt_failed(&mut self, now: Instant) -> RetryDecision {
+        if self.attempts >= self.max_attempts { return RetryDecision::GiveUp; }
+        let at = now + backoff_delay(self.attempts);
+        self.next_attempt = Some(at);
+        RetryDecision::RetryAt(at)
+    }
+}
+
+/// Exponential backoff after the n-th failed attempt (1-based), capped.
+pub fn backoff_delay(failed_attempts: u32) -> Duration {
+    let factor = 1u32.checked_shl(failed_attempts.saturating_sub(1)).unwrap_or(u32::MAX);
+    RESUME_RETRY_BASE_DELAY.saturating_mul(factor).min(RESUME_RETRY_MAX_DELAY)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDRESS: u64 = 0x0011_2233_4455;
+
+    #[test]
+    fn first_attempt_waits_for_settle_delay() {
+        let resumed = Instant::now();
+        let mut retry = ResumeReconnect::new(ADDRESS, "WH-1000XM4", 3, resumed);
+        assert!(!retry.take_due_attempt(resumed + RESUME_SETTLE_DELAY - Duration::from_millis(1)));
+        assert!(retry.take_due_attempt(resumed + RESUME_SETTLE_DELAY));
+        assert_eq!(retry.attempts(), 1);
+        // In flight: nothing scheduled until the attempt reports back
+        assert_eq!(retry.next_attempt(), None);
+        assert!(!retry.take_due_attempt(resumed + RESUME_RETRY_MAX_DELAY * 10));
+    }
+
+    #[test]
+    fn failed_attempts_back_off_then_give_up() {
+        let mut now = Instant::now();
+        let mut retry = ResumeReconnect::new(ADDRESS, "WH-1000XM4", 3, now);
+        now += RESUME_SETTLE_DELAY;
+        for attempt in 1..3 {
+            assert!(retry.take_due_attempt(now));
+            let RetryDecision::RetryAt(at) = retry.on_attempt_failed(now) else { panic!("gave up after attempt {attempt}") };
+            assert_eq!(at, now + backoff_delay(attempt));
+            assert!(!retry.take_due_attempt(at - Duration::from_millis(1)));
+            now = at;
+        }
+        assert!(retry.take_due_attempt(now));
+        assert_eq!(retry.on_attempt_failed(now), RetryDecision::GiveUp);
+        assert_eq!(retry.attempts(), 3);
+    }
+
+    #[test]
+    fn at_least_one_attempt() {
+        let now = Instant::now();
+        let mut retry = ResumeReconnect::new(ADDRESS, "WH-1000XM4", 0, now);
+        assert!(retry.take_due_attempt(now + RESUME_SETTLE_DELAY));
+        assert_eq!(retry.on_attempt_failed(now), RetryDecision::GiveUp);
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        assert_eq!(backoff_delay(1), RESUME_RETRY_BASE_DELAY);
+        assert_eq!(backoff_delay(2), RESUME_RETRY_BASE_DELAY * 2);
+        assert_eq!(backoff_delay(3), RESUME_RETRY_BASE_DELAY * 4);
+        assert_eq!(backoff_delay(10), RESUME_RETRY_MAX_DELAY);
+        assert_eq!(backoff_delay(u32::MAX), RESUME_RETRY_MAX_DELAY);
+    }
+}
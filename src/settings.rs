@@ -0,0 +1,246 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2025 Triffit
+
+//! Persisted user settings: `%APPDATA%\ShowBTConnect\settings.ini`.
+//! Plain `key = value` lines (`#` comments); unknown keys are ignored and
+//! missing keys keep their defaults, so the file is entirely optional.
+
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::time::Duration;
+use regex::{Regex, RegexBuilder};
+use crate::audio_device::AudioDevice;
+use crate::auto_select::OutputPriority;
+use crate::config::{DEFAULT_AUDIO_REFRESH_QUIET, DEFAULT_RESUME_RECONNECT_ATTEMPTS};
+use crate::device_order::SortMode;
+use crate::hotkeys::Hotkey;
+use crate::log_dbg;
+
+#[derive(Debug, Clone)]
+pub struct Settings {
+    /// Show a balloon when a connected device drops to this battery percent (None = off).
+    pub low_battery_threshold: Option<u8>,
+    /// Global hotkey toggling the Bluetooth radio, e.g. "Ctrl+Alt+B" (None = off).
+    pub radio_hotkey: Option<Hotkey>,
+    /// Global hotkey switching the default output to the next device in menu order (None = off).
+    pub cycle_output_hotkey: Option<Hotkey>,
+    /// After resume from sleep, reconnect the last connected Bluetooth headset and make it the default output.
+    pub reconnect_after_resume: bool,
+    /// Ranked preferred outputs; the best active one is kept as the default.
+    pub output_priority: OutputPriority,
+    /// Make the previous user-chosen output the default again when the current one goes away.
+    pub restore_previous_default: bool,
+    /// Upper bound for reconnect attempts after resume (backoff between attempts).
+    pub resume_reconnect_attempts: u32,
+    /// Quiet period before a burst of audio notifications triggers one menu refresh.
+    pub audio_refresh_quiet: Duration,
+    /// Per-endpoint aliases / hidden flags and name-based hide rules for the audio menus.
+    pub audio_rules: AudioDeviceRules,
+}
+
+/// Audio menu customisation. Endpoint IDs are compared case-insensitively;
+/// hide patterns match the endpoint's friendly name (not its alias).
+#[derive(Debug, Clone, Default)]
+pub struct AudioDeviceRules {
+    aliases: HashMap<String, String>, // lowercase endpoint ID -> display name
+    hidden: HashSet<String>,          // lowercase endpoint IDs
+    hide_patterns: Vec<Regex>,
+    hidden_types: Vec<String>,        // lowercase connection type / form factor names
+    /// Endpoint IDs listed first, in this order (`pin` lines).
+    pub pinned: Vec<String>,
+    pub sort: SortMode,
+    /// Also list unplugged endpoints (greyed out, after the active ones); disabled ones have their own submenu.
+    pub show_inactive: bool,
+    /// Prefix menu entries with an icon for their form factor.
+    pub type_icons: bool,
+}
+
+impl AudioDeviceRules {
+    pub fn alias(&self, device: &AudioDevice) -> Option<&str> {
+        self.aliases.get(&device.id.to_lowercase()).map(String::as_str)
+    }
+
+    pub fn is_hidden(&self, device: &AudioDevice) -> bool {
+        self.hidden.contains(&device.id.to_lowercase())
+            || self.hide_patterns.iter().any(|re| re.is_match(&device.name))
+            || self.hidden_types.iter().any(|t| t == device.connection.name() || t == device.form_factor.name())
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            low_battery_threshold: None,
+            radio_hotkey: None,
+            cycle_output_hotkey: None,
+            reconnect_after_resume: false,
+            output_priority: OutputPriority::default(),
+            restore_previous_default: false,
+            resume_reconnect_attempts: DEFAULT_RESUME_RECONNECT_ATTEMPTS,
+            audio_refresh_quiet: DEFAULT_AUDIO_REFRESH_QUIET,
+            audio_rules: AudioDeviceRules::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        let Some(path) = settings_path() else { return Self::default() };
+        match std::fs::read_to_string(&path) {
+            Ok(text) => Self::parse(&text),
+            Err(_e) => {
+                log_dbg!("settings: using defaults ({}: {_e})", path.display());
+                Self::default()
+            }
+        }
+    }
+
+    fn parse(text: &str) -> Self {
+        let mut settings = Self::default();
+        for (key, value) in entries(text) {
+            match key {
+                "low_battery_threshold" => settings.low_battery_threshold = value.parse::<u8>().ok().filter(|&t| t > 0 && t <= 100),
+                "radio_hotkey" => settings.radio_hotkey = Hotkey::parse(value),
+                "cycle_output_hotkey" => settings.cycle_output_hotkey = Hotkey::parse(value),
+                "reconnect_after_resume" => settings.reconnect_after_resume = parse_bool(value),
+                "output_priority" => settings.output_priority = OutputPriority::parse(value),
+                "restore_previous_default" => settings.restore_previous_default = parse_bool(value),
+                "resume_reconnect_attempts" => {
+                    if let Ok(n) = value.parse::<u32>() { settings.resume_reconnect_attempts = n.clamp(1, 10); }
+                }
+                "audio_refresh_quiet_ms" => {
+                    if let Ok(ms) = value.parse::<u64>() { settings.audio_refresh_quiet = Duration::from_millis(ms.min(2000)); }
+                }
+                "show_inactive_devices" => settings.audio_rules.show_inactive = parse_bool(value),
+                "pin" => settings.audio_rules.pinned.push(value.to_string()),
+                "audio_sort" => match SortMode::parse(value) {
+                    Some(mode) => settings.audio_rules.sort = mode,
+                    None => { log_dbg!("settings: unknown audio_sort '{value}'"); }
+                },
+                "hide_type" => settings.audio_rules.hidden_types.extend(value.split(',').map(|t| t.trim().to_lowercase()).filter(|t| !t.is_empty())),
+                "audio_type_icons" => settings.audio_rules.type_icons = parse_bool(value),
+                "hide_name" => match RegexBuilder::new(value).case_insensitive(true).build() {
+                    Ok(re) => settings.audio_rules.hide_patterns.push(re),
+                    Err(_e) => { log_dbg!("settings: invalid hide_name pattern '{value}': {_e}"); }
+                },
+                _ => {
+                    if let Some(id) = key.strip_prefix("alias.") {
+                        if !value.is_empty() { settings.audio_rules.aliases.insert(id.to_lowercase(), value.to_string()); }
+                    } else if let Some(id) = key.strip_prefix("hide.") {
+                        if parse_bool(value) { settings.audio_rules.hidden.insert(id.to_lowercase()); }
+                    } else {
+                        log_dbg!("settings: unknown key '{key}'");
+                    }
+                }
+            }
+        }
+        settings
+    }
+}
+
+/// Non-empty, non-comment `key = value` pairs (trimmed).
+fn entries(text: &str) -> impl Iterator<Item = (&str, &str)> {
+    text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .filter_map(|l| l.split_once('='))
+        .map(|(k, v)| (k.trim(), v.trim()))
+}
+
+fn parse_bool(value: &str) -> bool {
+    matches!(value.to_ascii_lowercase().as_str(), "true" | "yes" | "on" | "1")
+}
+
+fn settings_path() -> Option<PathBuf> {
+    app_data_dir().map(|dir| dir.join("settings.ini"))
+}
+
+/// `%APPDATA%\ShowBTConnect` (settings and small state files).
+pub fn app_data_dir() -> Option<PathBuf> {
+    std::env::var_os("APPDATA").map(|dir| PathBuf::from(dir).join("ShowBTConnect"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio_device::{ConnectionType, FormFactor};
+
+    const SAMPLE: &str = "
+# Audio menus
+alias.{0.0.0.00000000}.{AAAA} = Desk speakers
+alias.{0.0.0.00000000}.{bbbb} =
+hide.{0.0.0.00000000}.{CCCC} = yes
+hide.{0.0.0.00000000}.{dddd} = false
+hide_name = ^nvidia
+hide_name = ([unclosed
+hide_name = Oculus
+hide_type = HDMI, , spdif
+pin = {0.0.0.00000000}.{bbbb}
+pin = {0.0.0.00000000}.{aaaa}
+audio_sort = recent
+show_inactive_devices = on
+no_such_key = 1
+";
+
+    fn rules() -> AudioDeviceRules { Settings::parse(SAMPLE).audio_rules }
+
+    fn device(id: &str, name: &str) -> AudioDevice {
+        AudioDevice { connection: ConnectionType::Usb, form_factor: FormFactor::Speakers, ..AudioDevice::sample(id, name) }
+    }
+
+    #[test]
+    fn aliases_match_ids_case_insensitively() {
+        let rules = rules();
+        assert_eq!(rules.alias(&device("{0.0.0.00000000}.{aaaa}", "Speakers")), Some("Desk speakers"));
+        // Empty aliases are ignored
+        assert_eq!(rules.alias(&device("{0.0.0.00000000}.{bbbb}", "Speakers")), None);
+    }
+
+    #[test]
+    fn hidden_ids() {
+        let rules = rules();
+        assert!(rules.is_hidden(&device("{0.0.0.00000000}.{cccc}", "Speakers")));
+        assert!(!rules.is_hidden(&device("{0.0.0.00000000}.{DDDD}", "Speakers")));
+    }
+
+    #[test]
+    fn hide_name_patterns_repeat_and_skip_invalid_ones() {
+        let rules = rules();
+        assert_eq!(rules.hide_patterns.len(), 2);
+        assert!(rules.is_hidden(&device("a", "NVIDIA High Definition Audio")));
+        assert!(rules.is_hidden(&device("b", "Headphones (Oculus Virtual Audio Device)")));
+        assert!(!rules.is_hidden(&device("c", "Speakers (NVIDIA)")));
+        // Patterns match the friendly name, not the alias
+        let aliased = AudioDevice { alias: Some("Oculus".to_string()), ..device("d", "Speakers") };
+        assert!(!rules.is_hidden(&aliased));
+    }
+
+    #[test]
+    fn hide_type_matches_connection_and_form_factor() {
+        let rules = rules();
+        assert!(rules.is_hidden(&AudioDevice { connection: ConnectionType::Hdmi, ..device("a", "Monitor") }));
+        assert!(rules.is_hidden(&AudioDevice { form_factor: FormFactor::Spdif, ..device("b", "Digital Output") }));
+        assert!(!rules.is_hidden(&device("c", "USB DAC")));
+    }
+
+    #[test]
+    fn order_and_inactive_settings() {
+        let rules = rules();
+        assert_eq!(rules.pinned, ["{0.0.0.00000000}.{bbbb}", "{0.0.0.00000000}.{aaaa}"]);
+        assert_eq!(rules.sort, SortMode::Recent);
+        assert!(rules.show_inactive);
+        assert!(!rules.type_icons);
+    }
+
+    #[test]
+    fn invalid_values_keep_defaults_or_are_clamped() {
+        let settings = Settings::parse("low_battery_threshold = 0\nresume_reconnect_attempts = 50\naudio_refresh_quiet_ms = 10000\naudio_sort = random");
+        assert_eq!(settings.low_battery_threshold, None);
+        assert_eq!(settings.resume_reconnect_attempts, 10);
+        assert_eq!(settings.audio_refresh_quiet, Duration::from_secs(2));
+        assert_eq!(settings.audio_rules.sort, SortMode::System);
+        let empty = Settings::parse("");
+        assert_eq!(empty.resume_reconnect_attempts, DEFAULT_RESUME_RECONNECT_ATTEMPTS);
+        assert!(empty.audio_rules.hide_patterns.is_empty());
+    }
+}
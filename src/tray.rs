@@ -1,102 +1,704 @@
 // SPDX-License-Identifier: MIT
 // Copyright (c) 2025 Triffit
-use tray_icon::{Icon, TrayIcon, TrayIconBuilder, menu::{Menu, MenuItem, PredefinedMenuItem, Submenu}};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tray_icon::{Icon, TrayIcon, TrayIconBuilder, menu::{CheckMenuItem, IsMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu}};
 use crate::log_dbg;
-use crate::config::AppResult;
-use crate::audio_device::{enumerate_audio_devices, AudioDevice};
+use crate::config::{AppResult, VOLUME_STEP_PERCENT};
+use crate::audio_device::{AudioBackend, AudioDevice, AudioFlow, AudioRole, EndpointState, EndpointVolume};
+use crate::bluetooth::{enumerate_paired_devices, device_for_endpoint, BluetoothDevice};
+use crate::battery::read_battery_levels;
+use crate::radio::{RadioControl, RadioMenuModel, RadioState};
+use crate::settings::AudioDeviceRules;
+use crate::device_order::{order_devices, RecentDevices};
+use crate::menu_sync::{Entry, KeyedItems, Optional};
+use crate::endpoint_group::{group_endpoints, EndpointGroup};
+use crate::wave_format::{choice_label, DeviceFormats, WaveFormat, FORMAT_CHOICES};
 
 // (No longer needed - removed AUDIO_DEVICE_BASE_ID constant)
 
-// Simple struct to hold menu item identifiers and audio device list.
-pub struct TrayHandles {
-    pub about_id: String,
-    pub exit_id: String,
-    pub audio_devices: Vec<AudioDevice>,
-    pub audio_device_ids: Vec<String>, // Menu IDs for each audio device
+/// Per-device volume actions offered in the "Volume" submenu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VolumeAction {
+    ToggleMute,
+    Up,
+    Down,
+}
+
+/// In-app nearby device discovery (kept across tray refreshes).
+#[derive(Debug, Default)]
+pub enum Discovery {
+    #[default]
+    Idle,
+    Scanning,
+    Found(Vec<BluetoothDevice>),
+}
+
+/// The context menu, built once and synced with the device lists on every refresh.
+/// Top level: [Audio Devices] [Input Devices] [separator] Bluetooth Devices, radio, separator, About, Exit.
+struct TrayMenu {
+    menu: Menu,
+    output: AudioSection,
+    input: AudioSection,
+    audio_separator: Optional<PredefinedMenuItem>,
+    bluetooth: BluetoothSection,
+    radio_item: CheckMenuItem,
+    about_id: String,
+    exit_id: String,
+}
+
+/// "Audio Devices" / "Input Devices": one entry per physical device (`EndpointGroup`),
+/// then the grouped devices' individual endpoints, role-specific "Default device only" /
+/// "Communications device only" lists, per-endpoint volume and format controls and
+/// "Disable device"; absent when there are no devices, listed or disabled. Inactive
+/// devices (listed last) are greyed out, except disconnected Bluetooth ones (click =
+/// connect). They get no role, volume or format entries. Disabled endpoints are only
+/// listed in "Disabled devices" (click = enable). Entries are keyed by group key / endpoint ID.
+struct AudioSection {
+    submenu: Optional<Submenu>,
+    // Below the device entries, in menu order; the always-present ones are `Optional`s
+    // too, so every slot after the entries is placed by one running position.
+    separator: Optional<PredefinedMenuItem>,
+    endpoints_menu: Optional<Submenu>,
+    media_submenu: Optional<Submenu>, // Console + Multimedia only
+    communications_submenu: Optional<Submenu>,
+    volume_menu: Optional<Submenu>,
+    format_menu: Optional<Submenu>,
+    disable_menu: Optional<Submenu>,
+    disabled_menu: Optional<Submenu>,
+    devices: KeyedItems<String, MenuItem>,
+    endpoints: KeyedItems<String, MenuItem>, // click = all roles on that endpoint
+    media: KeyedItems<String, CheckMenuItem>,
+    communications: KeyedItems<String, CheckMenuItem>,
+    volume: KeyedItems<String, VolumeEntries>,
+    format: KeyedItems<String, FormatEntries>,
+    disable: KeyedItems<String, MenuItem>,
+    enable: KeyedItems<String, MenuItem>,
+    lock_item: Option<CheckMenuItem>, // outputs only
+    type_icons: bool,
+}
+
+/// A device's volume submenu: mute toggle and step up / down.
+struct VolumeEntries {
+    submenu: Submenu,
+    mute_item: MenuItem,
+    up_item: MenuItem,
+    down_item: MenuItem,
+}
+
+/// A device's format submenu: one entry per `FORMAT_CHOICES` element, checked when current,
+/// greyed out when the endpoint does not accept it.
+struct FormatEntries {
+    submenu: Submenu,
+    items: Vec<CheckMenuItem>,
+}
+
+/// "Bluetooth Devices": paired devices, then [(no paired devices)] separator [Connect and use]
+/// Pair new device…, Scan for nearby devices, [Nearby devices] [Unpair]. Keyed by address.
+struct BluetoothSection {
+    submenu: Submenu,
+    devices: KeyedItems<u64, MenuItem>,
+    placeholder: Optional<MenuItem>,
+    connect_use: Optional<Submenu>,
+    connect_use_items: KeyedItems<u64, MenuItem>,
+    pair_new_item: MenuItem,
+    scan_item: MenuItem,
+    nearby: Optional<Submenu>,
+    nearby_items: KeyedItems<u64, MenuItem>,
+    unpair: Optional<Submenu>,
+    unpair_items: KeyedItems<u64, MenuItem>,
 }
 
 pub struct TrayManager {
     icon: TrayIcon,
-    handles: TrayHandles,
+    menu: TrayMenu,
+    audio_devices: Vec<AudioDevice>,
+    audio_groups: Vec<EndpointGroup>,
+    disabled_outputs: Vec<AudioDevice>,
+    input_devices: Vec<AudioDevice>,
+    input_groups: Vec<EndpointGroup>,
+    disabled_inputs: Vec<AudioDevice>,
+    bluetooth_devices: Vec<BluetoothDevice>,
+    /// Volumes of the listed active endpoints, read when an endpoint is first listed and then
+    /// kept current by volume callbacks (`refresh_volumes`).
+    volumes: HashMap<String, Option<EndpointVolume>>,
+    /// Format probes of the listed active endpoints (a probe is one `IsFormatSupported` per
+    /// choice), dropped when `forget_format` reports a change.
+    formats: HashMap<String, Option<DeviceFormats>>,
+    radio: Arc<dyn RadioControl>,
+    /// Last known radio state (reading it blocks, so workers and `StateChanged` report it).
+    radio_state: RadioState,
+    audio: Arc<dyn AudioBackend>,
+    audio_rules: AudioDeviceRules,
+    recent: RecentDevices,
+    discovery: Discovery,
 }
 
 include!(concat!(env!("OUT_DIR"), "/icon_rgba.rs"));
 
 pub fn load_icon() -> AppResult<Icon> { Ok(Icon::from_rgba(ICON_RGBA.to_vec(), ICON_WIDTH, ICON_HEIGHT)?) }
 
-pub fn build_tray() -> AppResult<(TrayIcon, TrayHandles)> {
-    let icon = load_icon()?;
-    let menu = Menu::new();
-    
-    // Enumerate audio devices
-    let audio_devices = enumerate_audio_devices().unwrap_or_else(|_e| {
-        log_dbg!("tray: failed to enumerate audio devices: {}", _e);
+fn new_item() -> AppResult<MenuItem> { Ok(MenuItem::new("", true, None)) }
+
+fn new_check_item() -> AppResult<CheckMenuItem> { Ok(CheckMenuItem::new("", true, false, None)) }
+
+impl TrayMenu {
+    fn new(type_icons: bool) -> AppResult<Self> {
+        let menu = Menu::new();
+        let output = AudioSection::new("Audio Devices", true, type_icons)?;
+        let input = AudioSection::new("Input Devices", false, type_icons)?;
+        let audio_separator = Optional::hidden(PredefinedMenuItem::separator());
+        let bluetooth = BluetoothSection::new()?;
+        menu.append(&bluetooth.submenu)?;
+        let radio_item = CheckMenuItem::new("", false, false, None);
+        menu.append(&radio_item)?;
+        menu.append(&PredefinedMenuItem::separator())?;
+        let about_item = MenuItem::new("About", true, None);
+        let exit_item = MenuItem::new("Exit", true, None);
+        menu.append(&about_item)?;
+        menu.append(&exit_item)?;
+        let about_id = about_item.id().0.clone();
+        let exit_id = exit_item.id().0.clone();
+        Ok(Self { menu, output, input, audio_separator, bluetooth, radio_item, about_id, exit_id })
+    }
+
+    fn sync(&mut self, output: AudioLists, input: AudioLists, bluetooth_devices: &[BluetoothDevice], discovery: &Discovery) -> AppResult {
+        let mut position = 0;
+        self.output.sync(&self.menu, output, bluetooth_devices, &mut position)?;
+        self.input.sync(&self.menu, input, bluetooth_devices, &mut position)?;
+        self.audio_separator.show(&self.menu, output.is_listed() || input.is_listed(), &mut position)?;
+        self.bluetooth.sync(bluetooth_devices, discovery)?;
+        Ok(())
+    }
+
+    /// Radio toggle (state-derived label, disabled when no radio).
+    fn sync_radio(&self, radio: RadioMenuModel) {
+        self.radio_item.set_text(radio.text);
+        self.radio_item.set_enabled(radio.enabled);
+        self.radio_item.set_checked(radio.checked);
+    }
+}
+
+/// One flow's listed devices, their groups and its disabled devices.
+#[derive(Clone, Copy)]
+struct AudioLists<'a> {
+    devices: &'a [AudioDevice],
+    groups: &'a [EndpointGroup],
+    disabled: &'a [AudioDevice],
+}
+
+impl AudioLists<'_> {
+    fn is_listed(&self) -> bool { !self.devices.is_empty() || !self.disabled.is_empty() }
+}
+
+impl AudioSection {
+    fn new(title: &str, with_lock: bool, type_icons: bool) -> AppResult<Self> {
+        let submenu = Submenu::new(title, true);
+        let fixed = |text: &str| Optional::hidden(Submenu::new(text, true));
+        // Only the lock entry is appended here; `sync` inserts everything above it
+        let lock_item = with_lock.then(|| CheckMenuItem::new("Lock current device", true, false, None));
+        if let Some(item) = &lock_item {
+            submenu.append_items(&[&PredefinedMenuItem::separator(), item])?;
+        }
+        Ok(Self {
+            submenu: Optional::hidden(submenu), separator: Optional::hidden(PredefinedMenuItem::separator()),
+            endpoints_menu: fixed("Endpoints"), media_submenu: fixed("Default device only"),
+            communications_submenu: fixed("Communications device only"), volume_menu: fixed("Volume"),
+            format_menu: fixed("Format"), disable_menu: fixed("Disable device"), disabled_menu: fixed("Disabled devices"),
+            devices: KeyedItems::default(), endpoints: KeyedItems::default(), media: KeyedItems::default(),
+            communications: KeyedItems::default(), volume: KeyedItems::default(), format: KeyedItems::default(),
+            disable: KeyedItems::default(), enable: KeyedItems::default(), lock_item, type_icons,
+        })
+    }
+
+    fn sync(&mut self, menu: &Menu, lists: AudioLists, bluetooth_devices: &[BluetoothDevice], menu_position: &mut usize) -> AppResult {
+        let AudioLists { devices, groups, disabled } = lists;
+        let icons = self.type_icons;
+        self.devices.sync(&self.submenu.item, groups, |group| group.key.clone(), new_item, |item, group| {
+            let entry = group.entry(devices);
+            let connectable = entry.state == EndpointState::Unplugged && device_for_endpoint(&entry.name, bluetooth_devices).is_some();
+            item.set_text(audio_label(&entry, bluetooth_devices, icons));
+            item.set_enabled(entry.is_active() || connectable);
+        })?;
+        // Expand option: the endpoints of grouped devices, to pick one explicitly
+        let grouped: Vec<&AudioDevice> = groups.iter().filter(|g| g.is_group()).flat_map(|g| g.members.iter().map(|&idx| &devices[idx])).collect();
+        self.endpoints.sync(&self.endpoints_menu.item, &grouped, |device| device.id.clone(), new_item, |item, device| {
+            item.set_text(audio_label(device, bluetooth_devices, icons));
+        })?;
+        let mut position = self.devices.iter().count();
+        self.separator.show(&self.submenu.item, true, &mut position)?;
+        self.endpoints_menu.show(&self.submenu.item, !grouped.is_empty(), &mut position)?;
+        for fixed in [&mut self.media_submenu, &mut self.communications_submenu, &mut self.volume_menu, &mut self.format_menu, &mut self.disable_menu] {
+            fixed.show(&self.submenu.item, true, &mut position)?;
+        }
+        self.disabled_menu.show(&self.submenu.item, !disabled.is_empty(), &mut position)?;
+
+        let id = |device: &AudioDevice| device.id.clone();
+        let active = &devices[..devices.iter().take_while(|d| d.is_active()).count()];
+        self.media.sync(&self.media_submenu.item, active, id, new_check_item, |item, device| {
+            item.set_text(device.display_name());
+            item.set_checked(device.is_default);
+        })?;
+        self.communications.sync(&self.communications_submenu.item, active, id, new_check_item, |item, device| {
+            item.set_text(device.display_name());
+            item.set_checked(device.is_communications_default);
+        })?;
+        self.volume.sync(&self.volume_menu.item, active, id, VolumeEntries::new, VolumeEntries::update)?;
+        self.format.sync(&self.format_menu.item, active, id, FormatEntries::new, FormatEntries::update)?;
+
+        // System-wide enable / disable (IPolicyConfig::SetEndpointVisibility)
+        self.disable.sync(&self.disable_menu.item, devices, id, new_item, |item, device| item.set_text(device.display_name()))?;
+        self.disable_menu.item.set_enabled(!devices.is_empty());
+        self.enable.sync(&self.disabled_menu.item, disabled, id, new_item, |item, device| {
+            item.set_text(format!("Enable {}", device.display_name()));
+        })?;
+        self.submenu.show(menu, lists.is_listed(), menu_position)
+    }
+
+    /// Endpoints and roles to assign for a menu ID.
+    fn lookup<'a>(&self, menu_id: &str, devices: &'a [AudioDevice], groups: &[EndpointGroup]) -> Option<Vec<(&'a AudioDevice, &'static [AudioRole])>> {
+        if let Some(key) = self.devices.key_for(menu_id) {
+            return groups.iter().find(|g| g.key == *key).map(|g| g.assignments(devices));
+        }
+        let (all, media, communications): (&'static [AudioRole], &'static [AudioRole], &'static [AudioRole]) =
+            (&AudioRole::ALL, &AudioRole::MEDIA, &[AudioRole::Communications]);
+        let (id, roles) = self.endpoints.key_for(menu_id).map(|id| (id, all))
+            .or_else(|| self.media.key_for(menu_id).map(|id| (id, media)))
+            .or_else(|| self.communications.key_for(menu_id).map(|id| (id, communications)))?;
+        Some(vec![(devices.iter().find(|d| d.id == *id)?, roles)])
+    }
+
+    /// Endpoint ID and action for a volume menu ID.
+    fn volume_action(&self, menu_id: &str) -> Option<(&String, VolumeAction)> {
+        self.volume.iter().find_map(|(id, entries)| Some((id, entries.action(menu_id)?)))
+    }
+
+    /// Endpoint ID and whether to enable it, for a "Disable device" / "Disabled devices" menu ID.
+    fn visibility_change(&self, menu_id: &str) -> Option<(&String, bool)> {
+        self.disable.key_for(menu_id).map(|id| (id, false))
+            .or_else(|| self.enable.key_for(menu_id).map(|id| (id, true)))
+    }
+
+    /// Endpoint ID and `FORMAT_CHOICES` index for a format menu ID.
+    fn format_choice(&self, menu_id: &str) -> Option<(&String, usize)> {
+        self.format.iter().find_map(|(id, entries)| Some((id, entries.choice(menu_id)?)))
+    }
+
+    /// Show new volumes in the entries that include them.
+    fn update_volumes(&self, devices: &[AudioDevice], groups: &[EndpointGroup], bluetooth_devices: &[BluetoothDevice]) {
+        for group in groups {
+            if let Some(item) = self.devices.get(&group.key) { item.set_text(audio_label(&group.entry(devices), bluetooth_devices, self.type_icons)); }
+        }
+        for device in devices {
+            if let Some(item) = self.endpoints.get(&device.id) { item.set_text(audio_label(device, bluetooth_devices, self.type_icons)); }
+            if let Some(entries) = self.volume.get(&device.id) { entries.update(device); }
+        }
+    }
+}
+
+impl Entry for VolumeEntries {
+    fn slot(&self) -> &dyn IsMenuItem { &self.submenu }
+}
+
+impl VolumeEntries {
+    fn new() -> AppResult<Self> {
+        let submenu = Submenu::new("", true);
+        let mute_item = MenuItem::new("", true, None);
+        let up_item = MenuItem::new(format!("Volume up (+{VOLUME_STEP_PERCENT}%)"), true, None);
+        let down_item = MenuItem::new(format!("Volume down (-{VOLUME_STEP_PERCENT}%)"), true, None);
+        submenu.append_items(&[&mute_item, &up_item, &down_item])?;
+        Ok(Self { submenu, mute_item, up_item, down_item })
+    }
+
+    fn update(&self, device: &AudioDevice) {
+        let enabled = device.volume.is_some();
+        self.submenu.set_text(volume_title(device));
+        self.submenu.set_enabled(enabled);
+        self.mute_item.set_text(mute_text(device));
+        for item in [&self.mute_item, &self.up_item, &self.down_item] { item.set_enabled(enabled); }
+    }
+
+    fn action(&self, menu_id: &str) -> Option<VolumeAction> {
+        [(&self.mute_item, VolumeAction::ToggleMute), (&self.up_item, VolumeAction::Up), (&self.down_item, VolumeAction::Down)]
+            .into_iter()
+            .find(|(item, _)| item.id().0 == menu_id)
+            .map(|(_, action)| action)
+    }
+}
+
+impl Entry for FormatEntries {
+    fn slot(&self) -> &dyn IsMenuItem { &self.submenu }
+}
+
+impl FormatEntries {
+    fn new() -> AppResult<Self> {
+        let submenu = Submenu::new("", true);
+        let items: Vec<CheckMenuItem> = FORMAT_CHOICES.iter()
+            .map(|&(bits, rate)| CheckMenuItem::new(choice_label(bits, rate, false), true, false, None))
+            .collect();
+        for item in &items { submenu.append(item)?; }
+        Ok(Self { submenu, items })
+    }
+
+    fn update(&self, device: &AudioDevice) {
+        let current = device.formats.as_ref().map(|f| f.current);
+        self.submenu.set_text(format_title(device.display_name(), current));
+        self.submenu.set_enabled(current.is_some());
+        for (index, (item, &choice)) in self.items.iter().zip(&FORMAT_CHOICES).enumerate() {
+            let offered = device.formats.as_ref().is_some_and(|f| f.choices[index].is_some());
+            item.set_enabled(offered);
+            item.set_checked(current.is_some_and(|f| f.is_choice(choice)));
+        }
+    }
+
+    fn choice(&self, menu_id: &str) -> Option<usize> { self.items.iter().position(|item| item.id().0 == menu_id) }
+}
+
+impl BluetoothSection {
+    fn new() -> AppResult<Self> {
+        let submenu = Submenu::new("Bluetooth Devices", true);
+        let pair_new_item = MenuItem::new("Pair new device…", true, None);
+        let scan_item = MenuItem::new("Scan for nearby devices", true, None);
+        submenu.append_items(&[&PredefinedMenuItem::separator(), &pair_new_item, &scan_item])?;
+        Ok(Self {
+            submenu,
+            devices: KeyedItems::default(),
+            placeholder: Optional::hidden(MenuItem::new("(no paired devices)", false, None)),
+            // Connect + switch default output in one step (audio devices only)
+            connect_use: Optional::hidden(Submenu::new("Connect and use", true)),
+            connect_use_items: KeyedItems::default(),
+            pair_new_item,
+            scan_item,
+            nearby: Optional::hidden(Submenu::new("Nearby devices", true)),
+            nearby_items: KeyedItems::default(),
+            unpair: Optional::hidden(Submenu::new("Unpair", true)),
+            unpair_items: KeyedItems::default(),
+        })
+    }
+
+    fn sync(&mut self, devices: &[BluetoothDevice], discovery: &Discovery) -> AppResult {
+        let address = |device: &BluetoothDevice| device.address;
+        self.devices.sync(&self.submenu, devices, address, new_item, |item, device| item.set_text(bluetooth_label(device)))?;
+        let mut position = devices.len();
+        self.placeholder.show(&self.submenu, devices.is_empty(), &mut position)?;
+        position += 1; // separator
+
+        let audio: Vec<&BluetoothDevice> = devices.iter().filter(|d| d.is_audio()).collect();
+        self.connect_use_items.sync(&self.connect_use.item, &audio, |d| d.address, new_item, |item, device| item.set_text(&device.name))?;
+        self.connect_use.show(&self.submenu, !audio.is_empty(), &mut position)?;
+        position += 2; // "Pair new device…", scan
+
+        // Pairing: Windows wizard, in-app discovery of nearby devices, unpair (confirmed in main)
+        let scanning = matches!(discovery, Discovery::Scanning);
+        self.scan_item.set_text(if scanning { "Scanning for nearby devices…" } else { "Scan for nearby devices" });
+        self.scan_item.set_enabled(!scanning);
+        let nearby = match discovery { Discovery::Found(nearby) => nearby.as_slice(), _ => &[] };
+        self.nearby_items.sync(&self.nearby.item, nearby, address, new_item, |item, device| item.set_text(format!("Pair {}", device.name)))?;
+        self.nearby.item.set_text(format!("Nearby devices ({})", nearby.len()));
+        self.nearby.item.set_enabled(!nearby.is_empty());
+        self.nearby.show(&self.submenu, matches!(discovery, Discovery::Found(_)), &mut position)?;
+
+        self.unpair_items.sync(&self.unpair.item, devices, address, new_item, |item, device| item.set_text(&device.name))?;
+        self.unpair.show(&self.submenu, !devices.is_empty(), &mut position)
+    }
+}
+
+/// Enumerate one flow's audio devices, applying aliases, hide rules and the configured order.
+/// Returns the listed devices and, separately, the disabled ones.
+fn enumerate_audio(audio: &dyn AudioBackend, flow: AudioFlow, rules: &AudioDeviceRules, recent: &RecentDevices) -> (Vec<AudioDevice>, Vec<AudioDevice>) {
+    // Disabled endpoints are always read, for the "Disabled devices" submenu
+    let devices = audio.enumerate(flow, true).unwrap_or_else(|_e| {
+        log_dbg!("tray: failed to enumerate {flow:?} audio devices: {}", _e);
         Vec::new()
     });
+    let (mut disabled, mut devices): (Vec<AudioDevice>, Vec<AudioDevice>) = devices.into_iter()
+        .filter(|d| !rules.is_hidden(d))
+        .map(|mut d| { d.alias = rules.alias(&d).map(str::to_string); d })
+        .partition(|d| d.state == EndpointState::Disabled);
+    devices.retain(|d| rules.show_inactive || d.is_active());
+    order_devices(&mut devices, rules.sort, &rules.pinned, recent.ids());
+    order_devices(&mut disabled, rules.sort, &rules.pinned, recent.ids());
+    // Inactive endpoints go last (stable), so role, volume and format entries cover a prefix
+    devices.sort_by_key(|d| !d.is_active());
+    (devices, disabled)
+}
 
-    let audio_device_ids = if !audio_devices.is_empty() {
-        let audio_submenu = Submenu::new("Audio Devices", true);
-        let mut ids = Vec::new();
-        
-        for device in &audio_devices {
-            let label = if device.is_default {
-                format!("✓ {}", device.name)
-            } else {
-                format!("    {}", device.name)
-            };
-            let device_item = MenuItem::new(label, true, None);
-            ids.push(device_item.id().0.clone());
-            audio_submenu.append(&device_item)?;
-        }
-        
-        menu.append(&audio_submenu)?;
-        menu.append(&PredefinedMenuItem::separator())?;
-        ids
+fn read_formats(audio: &dyn AudioBackend, device: &AudioDevice) -> Option<DeviceFormats> {
+    audio.device_formats(&device.id).unwrap_or_else(|_e| {
+        log_dbg!("tray: failed to read format of {}: {}", device.name, _e);
+        None
+    })
+}
+
+fn audio_label(device: &AudioDevice, bluetooth_devices: &[BluetoothDevice], type_icon: bool) -> String {
+    let icon = if type_icon { format!("{} ", device.form_factor.icon()) } else { String::new() };
+    let battery = device_for_endpoint(&device.name, bluetooth_devices)
+        .map(BluetoothDevice::battery_suffix)
+        .unwrap_or_default();
+    let calls = if device.is_communications_default { " (communications)" } else { "" };
+    let volume = device.volume.map(EndpointVolume::suffix).unwrap_or_default();
+    let state = device.state.suffix();
+    if device.is_default {
+        format!("✓ {icon}{}{calls}{volume}{state}{battery}", device.display_name())
     } else {
-        Vec::new()
-    };
-    
-    let about_item = MenuItem::new("About", true, None);
-    let exit_item = MenuItem::new("Exit", true, None);
-    menu.append(&about_item)?; 
-    menu.append(&exit_item)?;
-    
-    let about_id = about_item.id().0.clone();
-    let exit_id = exit_item.id().0.clone();
-    
-    let tray_icon = TrayIconBuilder::new()
-        .with_menu(Box::new(menu))
-        .with_tooltip("Show Bluetooth Devices Panel")
-        .with_icon(icon)
-        .build()?;
-    
-    log_dbg!("tray: icon created with {} audio device(s)", audio_devices.len());
-    
-    Ok((tray_icon, TrayHandles { about_id, exit_id, audio_devices, audio_device_ids }))
+        format!("    {icon}{}{calls}{volume}{state}{battery}", device.display_name())
+    }
+}
+
+fn volume_title(device: &AudioDevice) -> String {
+    format!("{}{}", device.display_name(), device.volume.map(EndpointVolume::suffix).unwrap_or_default())
+}
+
+fn format_title(name: &str, current: Option<WaveFormat>) -> String {
+    match current {
+        Some(format) => format!("{name} ({})", format.label()),
+        None => format!("{name} (format unknown)"),
+    }
+}
+
+fn mute_text(device: &AudioDevice) -> &'static str {
+    if device.volume.is_some_and(|v| v.muted) { "Unmute" } else { "Mute" }
+}
+
+/// Record the current default devices (however they became default) as most recently used.
+fn note_current_defaults(audio: &dyn AudioBackend, recent: &mut RecentDevices) {
+    let defaults: Vec<String> = [AudioFlow::Output, AudioFlow::Input].into_iter()
+        .filter_map(|flow| audio.default_device(flow, AudioRole::Console).ok().flatten())
+        .collect();
+    recent.touch(&defaults);
+}
+
+fn bluetooth_label(device: &BluetoothDevice) -> String {
+    if device.connected {
+        format!("✓ {}{}", device.name, device.battery_suffix())
+    } else {
+        format!("    {}", device.name)
+    }
+}
+
+/// Tooltip with battery levels of connected devices (Windows truncates at 127 chars).
+fn tooltip_text(bluetooth_devices: &[BluetoothDevice]) -> String {
+    let mut text = String::from("Show Bluetooth Devices Panel");
+    for device in bluetooth_devices.iter().filter(|d| d.connected && d.battery.is_some()) {
+        text.push_str(&format!("\n{}{}", device.name, device.battery_suffix()));
+    }
+    text.chars().take(127).collect()
 }
 
 impl TrayManager {
-    pub fn new() -> AppResult<Self> {
-        let (icon, handles) = build_tray()?;
-        Ok(Self { icon, handles })
+    pub fn new(radio: Arc<dyn RadioControl>, audio: Arc<dyn AudioBackend>, audio_rules: AudioDeviceRules) -> AppResult<Self> {
+        let menu = TrayMenu::new(audio_rules.type_icons)?;
+        let icon = TrayIconBuilder::new()
+            .with_menu(Box::new(menu.menu.clone()))
+            .with_icon(load_icon()?)
+            .build()?;
+        let mut tray = Self {
+            icon, menu, audio_devices: Vec::new(), audio_groups: Vec::new(), disabled_outputs: Vec::new(),
+            input_devices: Vec::new(), input_groups: Vec::new(), disabled_inputs: Vec::new(),
+            bluetooth_devices: Vec::new(), volumes: HashMap::new(), formats: HashMap::new(),
+            radio, radio_state: RadioState::Unavailable, audio, audio_rules, recent: RecentDevices::load(), discovery: Discovery::Idle,
+        };
+        tray.menu.sync_radio(RadioMenuModel::from_state(tray.radio_state));
+        tray.refresh()?;
+        Ok(tray)
     }
-    pub fn about_id(&self) -> &str { &self.handles.about_id }
-    pub fn exit_id(&self) -> &str { &self.handles.exit_id }
-    
-    /// Get audio device by menu index
-    pub fn get_audio_device(&self, idx: usize) -> Option<&AudioDevice> {
-        self.handles.audio_devices.get(idx)
+    pub fn about_id(&self) -> &str { &self.menu.about_id }
+    pub fn exit_id(&self) -> &str { &self.menu.exit_id }
+    pub fn radio_toggle_id(&self) -> &str { &self.menu.radio_item.id().0 }
+    pub fn radio(&self) -> Arc<dyn RadioControl> { Arc::clone(&self.radio) }
+    pub fn radio_state(&self) -> RadioState { self.radio_state }
+
+    /// Show a radio state reported off the UI thread.
+    pub fn set_radio_state(&mut self, state: RadioState) {
+        if state == self.radio_state { return; }
+        self.radio_state = state;
+        self.menu.sync_radio(RadioMenuModel::from_state(state));
+    }
+    pub fn pair_new_id(&self) -> &str { &self.menu.bluetooth.pair_new_item.id().0 }
+    pub fn scan_id(&self) -> &str { &self.menu.bluetooth.scan_item.id().0 }
+
+    /// "Lock current device" entry of the Audio Devices submenu.
+    pub fn lock_id(&self) -> Option<&str> { self.menu.output.lock_item.as_ref().map(|item| item.id().0.as_str()) }
+
+    /// Show the lock state (the entry toggles itself on click; this keeps it in sync otherwise).
+    pub fn set_locked(&self, locked: bool) {
+        if let Some(item) = &self.menu.output.lock_item { item.set_checked(locked); }
+    }
+
+    /// Update discovery state; takes effect on the next `refresh()`.
+    pub fn set_discovery(&mut self, discovery: Discovery) { self.discovery = discovery; }
+
+    /// Check if a menu ID is a "Pair <device>" entry; returns the discovered device
+    pub fn nearby_device(&self, menu_id: &str) -> Option<&BluetoothDevice> {
+        let address = self.menu.bluetooth.nearby_items.key_for(menu_id)?;
+        match &self.discovery { Discovery::Found(nearby) => nearby.iter().find(|d| d.address == *address), _ => None }
+    }
+
+    /// Drop a device from the nearby list (after it was paired).
+    pub fn remove_nearby(&mut self, address: u64) {
+        if let Discovery::Found(nearby) = &mut self.discovery { nearby.retain(|d| d.address != address); }
+    }
+
+    /// Check if a menu ID is an "Unpair" entry; returns the paired device
+    pub fn unpair_device(&self, menu_id: &str) -> Option<&BluetoothDevice> {
+        let address = self.menu.bluetooth.unpair_items.key_for(menu_id)?;
+        self.bluetooth_devices.iter().find(|d| d.address == *address)
     }
     
-    /// Check if a menu ID corresponds to an audio device selection
-    pub fn audio_device_index(&self, menu_id: &str) -> Option<usize> {
-        self.handles.audio_device_ids.iter().position(|id| id == menu_id)
+    /// Currently listed audio output devices
+    pub fn audio_devices(&self) -> &[AudioDevice] { &self.audio_devices }
+
+    /// IDs of the listed active endpoints (both flows), sorted; volume callbacks cover these.
+    pub fn active_endpoint_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.audio_devices.iter().chain(&self.input_devices)
+            .filter(|d| d.is_active())
+            .map(|d| d.id.clone())
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    /// Output menu entries (one per physical device, see `EndpointGroup::entry`), in menu order
+    pub fn output_entries(&self) -> Vec<AudioDevice> {
+        self.audio_groups.iter().map(|g| g.entry(&self.audio_devices)).collect()
+    }
+
+    /// Endpoints and roles to assign for the output entry with this (media endpoint) ID, as a click on it does.
+    pub fn output_assignments(&self, entry_id: &str) -> Vec<(&AudioDevice, &'static [AudioRole])> {
+        self.audio_groups.iter()
+            .find(|g| self.audio_devices[g.media].id == entry_id)
+            .map(|g| g.assignments(&self.audio_devices))
+            .unwrap_or_default()
+    }
+
+    /// Check if a menu ID selects an output or input device; returns the endpoints with the roles to assign
+    pub fn audio_selection(&self, menu_id: &str) -> Option<Vec<(&AudioDevice, &'static [AudioRole])>> {
+        [(&self.menu.output, &self.audio_devices, &self.audio_groups), (&self.menu.input, &self.input_devices, &self.input_groups)]
+            .into_iter()
+            .find_map(|(section, devices, groups)| section.lookup(menu_id, devices, groups))
+    }
+
+    /// Get paired Bluetooth device by menu index
+    pub fn get_bluetooth_device(&self, idx: usize) -> Option<&BluetoothDevice> {
+        self.bluetooth_devices.get(idx)
+    }
+
+    /// Check if a menu ID corresponds to a Bluetooth device entry
+    pub fn bluetooth_device_index(&self, menu_id: &str) -> Option<usize> {
+        let address = self.menu.bluetooth.devices.key_for(menu_id)?;
+        self.bluetooth_devices.iter().position(|d| d.address == *address)
+    }
+
+    /// Currently listed paired Bluetooth devices
+    pub fn bluetooth_devices(&self) -> &[BluetoothDevice] { &self.bluetooth_devices }
+
+    /// Check if a menu ID is a "Connect and use" entry; returns the Bluetooth device index
+    pub fn connect_use_index(&self, menu_id: &str) -> Option<usize> {
+        let address = self.menu.bluetooth.connect_use_items.key_for(menu_id)?;
+        self.bluetooth_devices.iter().position(|d| d.address == *address)
     }
     
-    pub fn recreate(&mut self) -> AppResult {
-        let (icon, handles) = build_tray()?;
-        self.icon = icon; // old icon dropped here
-        self.handles = handles;
+    /// Apply a connection change from the Bluetooth watcher without re-enumerating devices.
+    /// Returns false if the device is not listed (e.g. newly paired) and a refresh is needed.
+    pub fn set_bluetooth_connected(&mut self, address: u64, connected: bool) -> bool {
+        let Some(device) = self.bluetooth_devices.iter_mut().find(|d| d.address == address) else { return false };
+        device.connected = connected;
+        device.battery = if connected { read_battery_levels(&[address]).first().map(|&(_, l)| l) } else { None };
+        if let Some(item) = self.menu.bluetooth.devices.get(&address) { item.set_text(bluetooth_label(device)); }
+        self.update_tooltip();
+        true
+    }
+
+    /// Check if a menu ID is a volume action; returns the device it applies to
+    pub fn volume_action(&self, menu_id: &str) -> Option<(&AudioDevice, VolumeAction)> {
+        [(&self.menu.output, &self.audio_devices), (&self.menu.input, &self.input_devices)]
+            .into_iter()
+            .find_map(|(section, devices)| {
+                let (endpoint_id, action) = section.volume_action(menu_id)?;
+                Some((devices.iter().find(|d| d.id == *endpoint_id)?, action))
+            })
+    }
+
+    /// Check if a menu ID disables or enables an endpoint; returns the device and whether to enable it
+    pub fn endpoint_visibility(&self, menu_id: &str) -> Option<(&AudioDevice, bool)> {
+        [(&self.menu.output, &self.audio_devices, &self.disabled_outputs), (&self.menu.input, &self.input_devices, &self.disabled_inputs)]
+            .into_iter()
+            .find_map(|(section, devices, disabled)| {
+                let (endpoint_id, enable) = section.visibility_change(menu_id)?;
+                Some((devices.iter().chain(disabled).find(|d| d.id == *endpoint_id)?, enable))
+            })
+    }
+
+    /// Check if a menu ID is a format choice; returns the device and the format layout it accepts
+    pub fn format_choice(&self, menu_id: &str) -> Option<(&AudioDevice, WaveFormat)> {
+        [(&self.menu.output, &self.audio_devices), (&self.menu.input, &self.input_devices)]
+            .into_iter()
+            .find_map(|(section, devices)| {
+                let (endpoint_id, index) = section.format_choice(menu_id)?;
+                let device = devices.iter().find(|d| d.id == *endpoint_id)?;
+                Some((device, device.formats.as_ref()?.choices[index]?))
+            })
+    }
+
+    /// Re-probe an endpoint's formats at the next refresh.
+    pub fn forget_format(&mut self, endpoint_id: &str) { self.formats.remove(endpoint_id); }
+
+    /// Re-read volumes of all listed devices and update their entries in place.
+    pub fn refresh_volumes(&mut self) {
+        let sections = [
+            (&mut self.audio_devices, &self.audio_groups, &self.menu.output),
+            (&mut self.input_devices, &self.input_groups, &self.menu.input),
+        ];
+        for (devices, groups, section) in sections {
+            let mut changed = false;
+            for device in devices.iter_mut().filter(|d| d.is_active()) {
+                let volume = self.audio.volume(&device.id).ok();
+                if volume == device.volume { continue; }
+                device.volume = volume;
+                self.volumes.insert(device.id.clone(), volume);
+                changed = true;
+            }
+            if changed { section.update_volumes(devices, groups, &self.bluetooth_devices); }
+        }
+    }
+
+    /// Re-enumerate all devices and sync the menu and tooltip with them in place
+    /// (same icon, same menu IDs for devices that are still listed).
+    pub fn refresh(&mut self) -> AppResult {
+        note_current_defaults(self.audio.as_ref(), &mut self.recent);
+        self.bluetooth_devices = enumerate_paired_devices().unwrap_or_else(|_e| {
+            log_dbg!("tray: failed to enumerate Bluetooth devices: {}", _e);
+            Vec::new()
+        });
+        (self.audio_devices, self.disabled_outputs) = enumerate_audio(self.audio.as_ref(), AudioFlow::Output, &self.audio_rules, &self.recent);
+        (self.input_devices, self.disabled_inputs) = enumerate_audio(self.audio.as_ref(), AudioFlow::Input, &self.audio_rules, &self.recent);
+        // Endpoints that stopped being active are dropped, so they are re-read when they return
+        let mut known_volumes = std::mem::take(&mut self.volumes);
+        let mut known_formats = std::mem::take(&mut self.formats);
+        for device in self.audio_devices.iter_mut().chain(&mut self.input_devices).filter(|d| d.is_active()) {
+            let volume = known_volumes.remove(&device.id).unwrap_or_else(|| self.audio.volume(&device.id).ok());
+            let formats = match known_formats.remove(&device.id) {
+                Some(formats) => formats,
+                None => read_formats(self.audio.as_ref(), device),
+            };
+            device.volume = volume;
+            device.formats.clone_from(&formats);
+            self.volumes.insert(device.id.clone(), volume);
+            self.formats.insert(device.id.clone(), formats);
+        }
+        self.audio_groups = group_endpoints(&self.audio_devices);
+        self.input_groups = group_endpoints(&self.input_devices);
+        let output = AudioLists { devices: &self.audio_devices, groups: &self.audio_groups, disabled: &self.disabled_outputs };
+        let input = AudioLists { devices: &self.input_devices, groups: &self.input_groups, disabled: &self.disabled_inputs };
+        self.menu.sync(output, input, &self.bluetooth_devices, &self.discovery)?;
+        self.update_tooltip();
+        log_dbg!("tray: menu synced with {} output / {} input device(s), {} Bluetooth device(s)", self.audio_devices.len(), self.input_devices.len(), self.bluetooth_devices.len());
         Ok(())
     }
-}
\ No newline at end of file
+
+    fn update_tooltip(&self) {
+        if let Err(_e) = self.icon.set_tooltip(Some(tooltip_text(&self.bluetooth_devices))) {
+            log_dbg!("tray: tooltip update failed: {_e}");
+        }
+    }
+}
@@ -0,0 +1,207 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2025 Triffit
+
+//! Endpoint stream formats (sample rate / bit depth). `WAVEFORMATEX` and
+//! `WAVEFORMATEXTENSIBLE` are handled as their little-endian byte layout, so
+//! building and parsing them needs no Windows API.
+
+/// Sample rate / bit depth combinations offered in the tray, as (bits, Hz).
+pub const FORMAT_CHOICES: [(u16, u32); 6] = [
+    (16, 44_100), (16, 48_000), (16, 96_000),
+    (24, 44_100), (24, 48_000), (24, 96_000),
+];
+
+const WAVE_FORMAT_PCM: u16 = 0x0001;
+const WAVE_FORMAT_IEEE_FLOAT: u16 = 0x0003;
+const WAVE_FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+/// `cbSize` of `WAVEFORMATEXTENSIBLE` (bytes after the 18-byte `WAVEFORMATEX` header).
+const EXTENSIBLE_EXTRA: u16 = 22;
+pub const WAVEFORMATEX_SIZE: usize = 18;
+const WAVEFORMATEXTENSIBLE_SIZE: usize = WAVEFORMATEX_SIZE + EXTENSIBLE_EXTRA as usize;
+/// `KSDATAFORMAT_SUBTYPE_*` GUIDs are `{0000xxxx-0000-0010-8000-00aa00389b71}` with the format tag
+/// in the first field; these are the bytes after that field.
+const SUBTYPE_SUFFIX: [u8; 12] = [0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0xaa, 0x00, 0x38, 0x9b, 0x71];
+
+/// An uncompressed PCM or float stream format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WaveFormat {
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// `SPEAKER_*` bits (0 = default layout for `channels`).
+    pub channel_mask: u32,
+    /// Valid bits per sample.
+    pub bits: u16,
+    /// Bits per sample in memory (24-bit audio is often carried in 32-bit containers).
+    pub container_bits: u16,
+    pub float: bool,
+}
+
+impl WaveFormat {
+    /// Parse a `WAVEFORMATEX` (with the `cbSize` extra bytes); None for compressed or
+    /// truncated formats.
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        let u16_at = |at: usize| Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?));
+        let u32_at = |at: usize| Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?));
+        let mut tag = u16_at(0)?;
+        let channels = u16_at(2)?;
+        let sample_rate = u32_at(4)?;
+        let container_bits = u16_at(14)?;
+        let mut format = Self { sample_rate, channels, channel_mask: 0, bits: container_bits, container_bits, float: false };
+        if tag == WAVE_FORMAT_EXTENSIBLE {
+            if u16_at(16)? < EXTENSIBLE_EXTRA || bytes.get(28..40)? != SUBTYPE_SUFFIX { return None; }
+            format.bits = u16_at(18)?;
+            format.channel_mask = u32_at(20)?;
+            tag = u16::try_from(u32_at(24)?).ok()?;
+        }
+        format.float = match tag { WAVE_FORMAT_PCM => false, WAVE_FORMAT_IEEE_FLOAT => true, _ => return None };
+        let valid = channels > 0 && sample_rate > 0 && format.bits > 0 && format.bits <= container_bits && container_bits % 8 == 0;
+        valid.then_some(format)
+    }
+
+    /// Build a `WAVEFORMATEXTENSIBLE` (what Windows stores as the device format).
+    pub fn encode(&self) -> [u8; WAVEFORMATEXTENSIBLE_SIZE] {
+        let block_align = self.channels * (self.container_bits / 8);
+        let tag = if self.float { WAVE_FORMAT_IEEE_FLOAT } else { WAVE_FORMAT_PCM };
+        let mask = if self.channel_mask == 0 { default_channel_mask(self.channels) } else { self.channel_mask };
+        let mut bytes = [0u8; WAVEFORMATEXTENSIBLE_SIZE];
+        bytes[0..2].copy_from_slice(&WAVE_FORMAT_EXTENSIBLE.to_le_bytes());
+        bytes[2..4].copy_from_slice(&self.channels.to_le_bytes());
+        bytes[4..8].copy_from_slice(&self.sample_rate.to_le_bytes());
+        bytes[8..12].copy_from_slice(&(self.sample_rate * u32::from(block_align)).to_le_bytes());
+        bytes[12..14].copy_from_slice(&block_align.to_le_bytes());
+        bytes[14..16].copy_from_slice(&self.container_bits.to_le_bytes());
+        bytes[16..18].copy_from_slice(&EXTENSIBLE_EXTRA.to_le_bytes());
+        bytes[18..20].copy_from_slice(&self.bits.to_le_bytes());
+        bytes[20..24].copy_from_slice(&mask.to_le_bytes());
+        bytes[24..28].copy_from_slice(&u32::from(tag).to_le_bytes());
+        bytes[28..40].copy_from_slice(&SUBTYPE_SUFFIX);
+        bytes
+    }
+
+    /// Integer PCM layouts of this format's channels at `bits` / `sample_rate`, preferred first
+    /// (24-bit packed, then in a 32-bit container).
+    pub fn pcm_layouts(&self, bits: u16, sample_rate: u32) -> Vec<Self> {
+        let layout = |container_bits| Self { sample_rate, bits, container_bits, float: false, ..*self };
+        if bits == 24 { vec![layout(24), layout(32)] } else { vec![layout(bits)] }
+    }
+
+    /// 32-bit float at the same rate and channels: the shared-mode mix format paired with a device format.
+    pub fn float_mix(&self) -> Self {
+        Self { bits: 32, container_bits: 32, float: true, ..*self }
+    }
+
+    /// Whether this is the (bits, Hz) choice, whatever the container.
+    pub fn is_choice(&self, (bits, sample_rate): (u16, u32)) -> bool {
+        !self.float && self.bits == bits && self.sample_rate == sample_rate
+    }
+
+    pub fn label(&self) -> String { choice_label(self.bits, self.sample_rate, self.float) }
+}
+
+/// "24 bit, 48000 Hz", as the Sound control panel lists formats.
+pub fn choice_label(bits: u16, sample_rate: u32, float: bool) -> String {
+    format!("{bits} bit{}, {sample_rate} Hz", if float { " float" } else { "" })
+}
+
+/// Windows' default speaker layout for a channel count (`KSAUDIO_SPEAKER_*`).
+fn default_channel_mask(channels: u16) -> u32 {
+    match channels {
+        1 => 0x4,     // front center
+        2 => 0x3,     // front left / right
+        4 => 0x33,    // quad
+        6 => 0x3F,    // 5.1
+        8 => 0x63F,   // 7.1 surround
+        n => (1u32 << n.min(18)) - 1,
+    }
+}
+
+/// A device's current format and, per `FORMAT_CHOICES` entry, the layout it accepts (if any).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceFormats {
+    pub current: WaveFormat,
+    pub choices: Vec<Option<WaveFormat>>,
+}
+
+impl DeviceFormats {
+    /// Offer each choice in the first layout `supports` accepts.
+    pub fn probe(current: WaveFormat, supports: impl Fn(&WaveFormat) -> bool) -> Self {
+        let choices = FORMAT_CHOICES.iter()
+            .map(|&(bits, rate)| current.pcm_layouts(bits, rate).into_iter().find(|layout| supports(layout)))
+            .collect();
+        Self { current, choices }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stereo(bits: u16, container_bits: u16, float: bool) -> WaveFormat {
+        WaveFormat { sample_rate: 48_000, channels: 2, channel_mask: 0x3, bits, container_bits, float }
+    }
+
+    #[test]
+    fn encode_decode_round_trip() {
+        for format in [stereo(16, 16, false), stereo(24, 24, false), stereo(32, 32, false), stereo(24, 32, false), stereo(32, 32, true)] {
+            let bytes = format.encode();
+            assert_eq!(WaveFormat::decode(&bytes), Some(format));
+            let block_align = u16::from_le_bytes([bytes[12], bytes[13]]);
+            assert_eq!(block_align, 2 * format.container_bits / 8);
+        }
+    }
+
+    #[test]
+    fn encode_fills_in_the_default_channel_mask() {
+        let format = WaveFormat { channel_mask: 0, channels: 6, ..stereo(16, 16, false) };
+        assert_eq!(WaveFormat::decode(&format.encode()).map(|f| f.channel_mask), Some(0x3F));
+    }
+
+    #[test]
+    fn decode_plain_waveformatex() {
+        let mut bytes = [0u8; WAVEFORMATEX_SIZE];
+        bytes[0..2].copy_from_slice(&WAVE_FORMAT_PCM.to_le_bytes());
+        bytes[2..4].copy_from_slice(&2u16.to_le_bytes());
+        bytes[4..8].copy_from_slice(&44_100u32.to_le_bytes());
+        bytes[14..16].copy_from_slice(&16u16.to_le_bytes());
+        let format = WaveFormat::decode(&bytes).unwrap();
+        assert_eq!((format.bits, format.container_bits, format.sample_rate, format.float), (16, 16, 44_100, false));
+        // Compressed formats are not offered
+        bytes[0..2].copy_from_slice(&0x0055u16.to_le_bytes());
+        assert_eq!(WaveFormat::decode(&bytes), None);
+    }
+
+    #[test]
+    fn decode_rejects_truncated_input() {
+        let bytes = stereo(24, 32, false).encode();
+        assert_eq!(WaveFormat::decode(&bytes[..WAVEFORMATEX_SIZE]), None);
+        assert_eq!(WaveFormat::decode(&bytes[..WAVEFORMATEXTENSIBLE_SIZE - 1]), None);
+        assert_eq!(WaveFormat::decode(&bytes[..10]), None);
+        assert_eq!(WaveFormat::decode(&[]), None);
+    }
+
+    #[test]
+    fn decode_rejects_unknown_subtype() {
+        let mut bytes = stereo(16, 16, false).encode();
+        bytes[30] ^= 0xFF;
+        assert_eq!(WaveFormat::decode(&bytes), None);
+        let mut bytes = stereo(16, 16, false).encode();
+        bytes[24..28].copy_from_slice(&0x0092u32.to_le_bytes()); // Dolby AC-3 over S/PDIF
+        assert_eq!(WaveFormat::decode(&bytes), None);
+    }
+
+    #[test]
+    fn decode_rejects_more_valid_bits_than_the_container() {
+        let mut bytes = stereo(24, 24, false).encode();
+        bytes[18..20].copy_from_slice(&32u16.to_le_bytes());
+        assert_eq!(WaveFormat::decode(&bytes), None);
+    }
+
+    #[test]
+    fn probe_offers_the_first_accepted_layout() {
+        let formats = DeviceFormats::probe(stereo(16, 16, false), |f| f.container_bits == 32 || f.bits == 16);
+        let index = |choice| FORMAT_CHOICES.iter().position(|&c| c == choice).unwrap();
+        assert_eq!(formats.choices[index((16, 44_100))], Some(WaveFormat { sample_rate: 44_100, ..stereo(16, 16, false) }));
+        assert_eq!(formats.choices[index((24, 96_000))], Some(WaveFormat { sample_rate: 96_000, ..stereo(24, 32, false) }));
+        assert!(formats.choices.iter().all(|layout| layout.is_some_and(|f| f.is_choice((f.bits, f.sample_rate)))));
+    }
+}